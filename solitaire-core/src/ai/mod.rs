@@ -0,0 +1,356 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+use std::time::{Duration, Instant};
+
+use board::Board;
+use rules::{AutomovePolicy, Move};
+use types::{Stack, StackRole, Suite};
+use variant::Variant;
+
+mod difficulty;
+mod packed;
+
+pub use self::difficulty::Difficulty;
+use self::packed::card_from_code;
+pub use self::packed::PackedBoard;
+
+pub enum AiResult {
+    Unknown,
+    Winable(Vec<Move>),
+    Lost,
+}
+
+/// Effort spent on a single search. The search gives up with `AiResult::Unknown` when it runs
+/// out of iterations or time.
+#[derive(Copy, Clone, Debug)]
+pub struct Budget {
+    pub iterations: usize,
+    pub time: Option<Duration>,
+    /// How strongly the search is drawn towards the goal. A weight of 1 finds the shortest
+    /// solution, a weight of `w` finds one at most `w` times as long, but much faster.
+    pub weight: u32,
+}
+
+impl Budget {
+    pub fn iterations(iterations: usize) -> Budget {
+        Budget {
+            iterations,
+            time: None,
+            weight: 2,
+        }
+    }
+}
+
+/// Work done by a search.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Stats {
+    /// States taken from the queue and expanded.
+    pub expanded: usize,
+    /// Distinct positions seen, after merging equivalent ones.
+    pub visited: usize,
+    /// Expanded states that led to no new position.
+    pub dead_ends: usize,
+}
+
+#[derive(Copy, Clone, Hash, Eq, PartialEq, Debug)]
+pub struct AiState {
+    board: PackedBoard,
+    policy: AutomovePolicy,
+    variant: Variant,
+}
+
+/// A state reached during the search, along with the move that led to it.
+struct Node {
+    state: AiState,
+    parent: Option<usize>,
+    m: Option<Move>,
+    cost: u32,
+}
+
+impl AiState {
+    /// Returns `None` if the stacks do not fit into a `PackedBoard`. The search assumes the safe
+    /// automove policy and the standard rules.
+    pub fn new(stacks: &[Stack]) -> Option<AiState> {
+        PackedBoard::from_stacks(stacks).map(|board| AiState {
+            board,
+            policy: AutomovePolicy::Safe,
+            variant: Variant::Standard,
+        })
+    }
+
+    /// Search for moves under another automove policy. The moves the policy makes on its own
+    /// are part of the solution, as they are for the safe policy.
+    pub fn with_policy(self, policy: AutomovePolicy) -> AiState {
+        AiState { policy, ..self }
+    }
+
+    /// Search for moves under the rules of another variant.
+    pub fn with_variant(self, variant: Variant) -> AiState {
+        AiState { variant, ..self }
+    }
+
+    /// Search for a short winning sequence of moves.
+    ///
+    /// This is a best-first search ordered by moves made so far plus the weighted `heuristic`.
+    /// Since the heuristic never overestimates, the solution is at most `budget.weight` times as
+    /// long as the shortest one.
+    pub fn astar(&self, budget: Budget) -> AiResult {
+        self.search(budget).0
+    }
+
+    /// Like `astar`, but also report how much work the search took.
+    pub fn search(&self, budget: Budget) -> (AiResult, Stats) {
+        let start = Instant::now();
+        let mut iterations = budget.iterations;
+        let mut stats = Stats::default();
+
+        let h = self.heuristic();
+        let mut nodes = vec![Node {
+            state: *self,
+            parent: None,
+            m: None,
+            cost: 0,
+        }];
+
+        // lowest known cost for each state
+        let mut best = HashMap::new();
+        best.insert(self.key(), 0);
+
+        // ordered by estimated total moves first, then by estimated remaining moves
+        let mut queue = BinaryHeap::new();
+        queue.push(Reverse((h, h, 0)));
+
+        while let Some(Reverse((_, _, idx))) = queue.pop() {
+            let cost = nodes[idx].cost;
+            if best[&nodes[idx].state.key()] < cost {
+                continue; // a cheaper way to this state was found in the meantime
+            }
+
+            if nodes[idx].state.is_won() {
+                return (AiResult::Winable(solution(&nodes, idx)), stats);
+            }
+
+            if iterations == 0 {
+                return (AiResult::Unknown, stats);
+            }
+            iterations -= 1;
+
+            if let Some(limit) = budget.time {
+                if start.elapsed() >= limit {
+                    return (AiResult::Unknown, stats);
+                }
+            }
+
+            stats.expanded += 1;
+            let state = nodes[idx].state;
+            let board = state.board();
+            let known = nodes.len();
+            for m in board.possible_moves() {
+                let state = match state.apply_move(&board, m) {
+                    Some(state) => state,
+                    None => continue,
+                };
+                let key = state.key();
+                if let Some(&known) = best.get(&key) {
+                    if known <= cost + 1 {
+                        continue;
+                    }
+                }
+
+                let h = state.heuristic();
+                best.insert(key, cost + 1);
+                stats.visited = best.len();
+                nodes.push(Node {
+                    state,
+                    parent: Some(idx),
+                    m: Some(m),
+                    cost: cost + 1,
+                });
+                queue.push(Reverse((cost + 1 + h * budget.weight, h, nodes.len() - 1)));
+            }
+            if nodes.len() == known {
+                stats.dead_ends += 1;
+            }
+        }
+        (AiResult::Lost, stats)
+    }
+
+    /// The same for all positions that only differ in which stack of a role holds which cards.
+    fn key(&self) -> PackedBoard {
+        self.board.canonical()
+    }
+
+    fn board(&self) -> Board {
+        Board::new(self.board.to_stacks())
+            .with_policy(self.policy)
+            .with_variant(self.variant)
+    }
+
+    /// The state after making one of the possible moves of `board`, the board of this state.
+    fn apply_move(&self, board: &Board, m: Move) -> Option<AiState> {
+        let mut board = board.clone();
+        board.apply(m);
+        Some(AiState {
+            board: PackedBoard::from_stacks(board.stacks())?,
+            ..*self
+        })
+    }
+
+    fn is_won(&self) -> bool {
+        self.board.piles().all(|(role, cards)| match role {
+            StackRole::Sorting => cards.iter().all(|&c| card_from_code(c) == Suite::FaceDown),
+            StackRole::Target => cards.len() == 9,
+            _ => true,
+        })
+    }
+
+    /// Lower bound on the number of moves needed to win.
+    ///
+    /// Every number card still on the table needs one move to a target stack, the flower needs
+    /// one move to its slot, and every color of dragons needs one button press. No move does
+    /// more than one of these at a time, so the estimate is admissible and consistent.
+    fn heuristic(&self) -> u32 {
+        let mut h = 0;
+        let mut dragons = [false; 3];
+        for (role, cards) in self.board.piles() {
+            for card in cards.iter().map(|&c| card_from_code(c)) {
+                match (role, card) {
+                    (StackRole::Target, _) | (StackRole::Flower, _) => {}
+                    (_, Suite::Number(_, _)) | (_, Suite::Flower) => h += 1,
+                    (_, Suite::Dragon(c)) => dragons[c as usize] = true,
+                    _ => {}
+                }
+            }
+
+            // a card above a lower card of the same color can not go to its target stack
+            // directly, so at least one move has to take cards off that stack elsewhere first
+            if role == StackRole::Sorting && is_blocked(cards) {
+                h += 1;
+            }
+        }
+        h + dragons.iter().filter(|&&d| d).count() as u32
+    }
+}
+
+fn is_blocked(cards: &[u8]) -> bool {
+    let cards = cards.iter().map(|&c| card_from_code(c)).collect::<Vec<_>>();
+    cards.iter().enumerate().any(|(i, &lower)| match lower {
+        Suite::Number(ln, lc) => cards[i + 1..].iter().any(|&upper| match upper {
+            Suite::Number(un, uc) => uc == lc && un > ln,
+            _ => false,
+        }),
+        _ => false,
+    })
+}
+
+/// Follow the chain of moves back to the initial state.
+fn solution(nodes: &[Node], mut idx: usize) -> Vec<Move> {
+    let mut path = Vec::new();
+    while let (Some(parent), Some(m)) = (nodes[idx].parent, nodes[idx].m) {
+        path.push(m);
+        idx = parent;
+    }
+    path.reverse();
+    path
+}
+
+/// Solve a deal of the standard rules that tests rely on being winnable.
+#[cfg(test)]
+pub(crate) fn solve_deal(seed: u64) -> Vec<Move> {
+    use deal;
+
+    let ai = AiState::new(&deal::deal(seed, Variant::Standard)).unwrap();
+    match ai.astar(Budget::iterations(10_000)) {
+        AiResult::Winable(moves) => moves,
+        _ => panic!("deal {:x} should be winnable", seed),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use deal;
+    use rules;
+
+    #[test]
+    fn solution_wins() {
+        let stacks = deal::deal(5, Variant::Standard);
+        for &policy in &AutomovePolicy::ALL {
+            let ai = AiState::new(&stacks).unwrap().with_policy(policy);
+            match ai.astar(Budget::iterations(100_000)) {
+                AiResult::Winable(moves) => {
+                    let board = Board::new(stacks.clone()).with_policy(policy);
+                    assert!(rules::is_solution(&board, &moves));
+                    assert!(!rules::is_solution(&board, &moves[1..]));
+                    assert!(!rules::is_solution(&board, &moves[..moves.len() - 1]));
+                }
+                _ => panic!("deal 5 should be winnable with automove {}", policy),
+            }
+        }
+    }
+
+    #[test]
+    fn no_iterations() {
+        let ai = AiState::new(&deal::deal(5, Variant::Standard)).unwrap();
+        match ai.search(Budget::iterations(0)) {
+            (AiResult::Unknown, stats) => assert_eq!(stats.expanded, 0),
+            _ => panic!("the search should give up right away"),
+        }
+    }
+
+    #[test]
+    fn variants() {
+        for &variant in &Variant::ALL {
+            let stacks = deal::deal(0x16, variant);
+            let ai = AiState::new(&stacks).unwrap().with_variant(variant);
+            match ai.astar(Budget::iterations(10_000)) {
+                AiResult::Winable(moves) => {
+                    let board = Board::new(stacks).with_variant(variant);
+                    assert!(rules::is_solution(&board, &moves));
+                }
+                _ => panic!("deal 16 should be winnable as {}", variant),
+            }
+        }
+    }
+
+    #[test]
+    fn equivalent_keys() {
+        let stacks = deal::deal(7, Variant::Standard);
+        let mut swapped = stacks.clone();
+        swapped.swap(0, 5);
+        let mut swapped = Board::new(swapped);
+        swapped.apply_move(Move::Cards(9, 1, 1)).unwrap();
+        let mut moved = Board::new(stacks.clone());
+        moved.apply_move(Move::Cards(10, 1, 1)).unwrap();
+
+        let key = |stacks: &[Stack]| AiState::new(stacks).unwrap().key();
+        assert_eq!(key(swapped.stacks()), key(moved.stacks()));
+        assert_ne!(key(&stacks), key(swapped.stacks()));
+    }
+
+    #[test]
+    fn shortest_solution() {
+        use types::Color::*;
+        use types::Suite::*;
+
+        // everything is sorted except for the last card of each color
+        let mut stacks = Variant::Standard.empty_table();
+        for (t, &color) in [Red, Green, White].iter().enumerate() {
+            for n in 1..9 {
+                stacks[12 + t].push_card(Number(n, color));
+            }
+            stacks[8 + t].cards = vec![FaceDown; 4];
+        }
+        stacks[11].push_card(Flower);
+        stacks[0].push_card(Number(9, Red));
+        stacks[0].push_card(Number(9, Green));
+        stacks[1].push_card(Number(9, White));
+
+        let mut budget = Budget::iterations(1000);
+        budget.weight = 1;
+        match AiState::new(&stacks).unwrap().astar(budget) {
+            AiResult::Winable(moves) => assert_eq!(moves.len(), 3),
+            _ => panic!("position should be winnable"),
+        }
+    }
+}
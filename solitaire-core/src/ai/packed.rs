@@ -0,0 +1,172 @@
+use types::{Color, Stack, StackRole, Suite};
+
+/// Most stacks a board can hold, enough for the full table.
+pub const MAX_STACKS: usize = 15;
+/// Most cards a board can hold, enough for a full deck.
+pub const MAX_CARDS: usize = 40;
+
+/// A table packed into a fixed amount of memory, one byte per card.
+///
+/// All cards are stored back to back, bottom to top and stack after stack. `ends[i]` is the
+/// index just past the top card of stack `i`. Unused slots are kept at zero, so two boards are
+/// equal exactly if they hold the same stacks.
+#[derive(Copy, Clone, Hash, Eq, PartialEq, Debug)]
+pub struct PackedBoard {
+    cards: [u8; MAX_CARDS],
+    ends: [u8; MAX_STACKS],
+    roles: [StackRole; MAX_STACKS],
+    len: u8,
+}
+
+impl PackedBoard {
+    fn empty() -> PackedBoard {
+        PackedBoard {
+            cards: [0; MAX_CARDS],
+            ends: [0; MAX_STACKS],
+            roles: [StackRole::Generic; MAX_STACKS],
+            len: 0,
+        }
+    }
+
+    /// Pack a list of stacks. Returns `None` if they hold too many stacks or cards.
+    pub fn from_stacks(stacks: &[Stack]) -> Option<PackedBoard> {
+        if stacks.len() > MAX_STACKS {
+            return None;
+        }
+        let mut board = PackedBoard::empty();
+        for stack in stacks {
+            let cards = stack.iter().map(|&c| card_code(c)).collect::<Vec<_>>();
+            board.push_pile(stack.role, &cards)?;
+        }
+        Some(board)
+    }
+
+    pub fn to_stacks(&self) -> Vec<Stack> {
+        self.piles()
+            .map(|(role, cards)| {
+                let mut stack = Stack::new(role);
+                for &c in cards {
+                    stack.push_card(card_from_code(c));
+                }
+                stack
+            })
+            .collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.len as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Role and cards of every stack, in order.
+    pub fn piles<'a>(&'a self) -> impl Iterator<Item = (StackRole, &'a [u8])> + Clone + 'a {
+        (0..self.len()).map(move |i| (self.roles[i], self.pile(i)))
+    }
+
+    pub fn role(&self, i: usize) -> StackRole {
+        self.roles[i]
+    }
+
+    pub fn pile(&self, i: usize) -> &[u8] {
+        let start = if i == 0 { 0 } else { self.ends[i - 1] as usize };
+        &self.cards[start..self.ends[i] as usize]
+    }
+
+    /// The same board with the stacks of each role sorted by their cards.
+    ///
+    /// Which sorting column, free cell or target stack holds a certain pile of cards does not
+    /// matter for the rest of the game, so all boards that only differ in that have the same
+    /// canonical form.
+    pub fn canonical(&self) -> PackedBoard {
+        let mut order = (0..self.len()).collect::<Vec<_>>();
+        order.sort_by(|&a, &b| {
+            let key = |i: usize| (role_code(self.roles[i]), self.pile(i));
+            key(a).cmp(&key(b))
+        });
+
+        let mut board = PackedBoard::empty();
+        for i in order {
+            board.push_pile(self.roles[i], self.pile(i));
+        }
+        board
+    }
+
+    fn push_pile(&mut self, role: StackRole, cards: &[u8]) -> Option<()> {
+        let i = self.len();
+        let start = if i == 0 { 0 } else { self.ends[i - 1] as usize };
+        if i >= MAX_STACKS || start + cards.len() > MAX_CARDS {
+            return None;
+        }
+        self.cards[start..start + cards.len()].copy_from_slice(cards);
+        self.ends[i] = (start + cards.len()) as u8;
+        self.roles[i] = role;
+        self.len += 1;
+        Some(())
+    }
+}
+
+fn color_code(c: Color) -> u8 {
+    match c {
+        Color::Red => 0,
+        Color::Green => 1,
+        Color::White => 2,
+    }
+}
+
+fn color_from_code(c: u8) -> Color {
+    match c {
+        0 => Color::Red,
+        1 => Color::Green,
+        _ => Color::White,
+    }
+}
+
+pub fn card_code(card: Suite) -> u8 {
+    match card {
+        Suite::FaceDown => 0,
+        Suite::Flower => 1,
+        Suite::Dragon(c) => 2 + color_code(c),
+        Suite::Number(n, c) => 5 + 9 * color_code(c) + (n - 1),
+    }
+}
+
+pub fn card_from_code(code: u8) -> Suite {
+    match code {
+        0 => Suite::FaceDown,
+        1 => Suite::Flower,
+        2..=4 => Suite::Dragon(color_from_code(code - 2)),
+        _ => Suite::Number((code - 5) % 9 + 1, color_from_code((code - 5) / 9)),
+    }
+}
+
+fn role_code(role: StackRole) -> u8 {
+    match role {
+        StackRole::Sorting => 0,
+        StackRole::Dragon => 1,
+        StackRole::Flower => 2,
+        StackRole::Target => 3,
+        StackRole::Generic => 4,
+        StackRole::Animation => 5,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use deal;
+    use variant::Variant;
+
+    #[test]
+    fn roundtrip() {
+        let stacks = deal::deal(3, Variant::Standard);
+        let board = PackedBoard::from_stacks(&stacks).unwrap();
+        assert_eq!(board.to_stacks(), stacks);
+
+        let mut too_many = stacks.clone();
+        too_many[0].push_card(Suite::Flower);
+        assert!(PackedBoard::from_stacks(&too_many).is_none());
+    }
+}
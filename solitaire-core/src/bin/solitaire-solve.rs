@@ -0,0 +1,254 @@
+extern crate solitaire_core;
+
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::process;
+use std::time::{Duration, Instant};
+
+use solitaire_core::ai::{AiResult, AiState, Budget};
+use solitaire_core::board::Board;
+use solitaire_core::rules::{self, AutomovePolicy, Move};
+use solitaire_core::types::{Stack, StackRole, Suite};
+use solitaire_core::variant::Variant;
+use solitaire_core::{notation, savegame};
+
+const USAGE: &str = "Usage: solitaire-solve [OPTIONS] <SEED|FILE>...
+
+Runs the solver on each deal and prints the winning move sequence.
+A deal is either a seed as shown in the game, a file with a board in the
+notation of the `notation` module, or a save game. Exits with status 1 if a
+deal could not be loaded or its solution does not work, and 2 on bad options.
+
+Options:
+    -i, --iterations N    give up after expanding N states (default 100000)
+    -t, --time SECONDS    give up after SECONDS per deal
+    -w, --weight W        accept solutions up to W times longer than the
+                          shortest one in exchange for speed (default 2)
+    -a, --automove P      which cards move to the foundations on their own:
+                          off, safe or aggressive (default safe)
+    -v, --variant V       the rules to play by: standard, no-flower,
+                          two-cells or dragons-anywhere (default standard)
+    -q, --quiet           print only one summary line per deal
+    -s, --stats           print how many positions each search looked at
+    -p, --print           print each board in notation before solving it
+    -h, --help            show this message";
+
+struct Options {
+    budget: Budget,
+    policy: AutomovePolicy,
+    variant: Variant,
+    quiet: bool,
+    stats: bool,
+    print: bool,
+    deals: Vec<String>,
+}
+
+fn parse_args() -> Result<Options, String> {
+    let mut options = Options {
+        budget: Budget::iterations(100_000),
+        policy: AutomovePolicy::Safe,
+        variant: Variant::Standard,
+        quiet: false,
+        stats: false,
+        print: false,
+        deals: Vec::new(),
+    };
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-i" | "--iterations" => {
+                let n = args.next().ok_or("missing value for --iterations")?;
+                options.budget.iterations = n
+                    .parse()
+                    .map_err(|_| format!("invalid iteration count: {}", n))?;
+            }
+            "-t" | "--time" => {
+                let t = args.next().ok_or("missing value for --time")?;
+                let secs: f64 = t.parse().map_err(|_| format!("invalid time: {}", t))?;
+                options.budget.time = Some(Duration::from_millis((secs * 1000.0) as u64));
+            }
+            "-w" | "--weight" => {
+                let w = args.next().ok_or("missing value for --weight")?;
+                options.budget.weight = w.parse().map_err(|_| format!("invalid weight: {}", w))?;
+            }
+            "-a" | "--automove" => {
+                let p = args.next().ok_or("missing value for --automove")?;
+                options.policy = p
+                    .parse()
+                    .map_err(|_| format!("invalid automove policy: {}", p))?;
+            }
+            "-v" | "--variant" => {
+                let v = args.next().ok_or("missing value for --variant")?;
+                options.variant = v.parse().map_err(|_| format!("invalid variant: {}", v))?;
+            }
+            "-q" | "--quiet" => options.quiet = true,
+            "-s" | "--stats" => options.stats = true,
+            "-p" | "--print" => options.print = true,
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                process::exit(0);
+            }
+            _ if arg.starts_with('-') => return Err(format!("unknown option: {}", arg)),
+            _ => options.deals.push(arg),
+        }
+    }
+
+    if options.deals.is_empty() {
+        return Err("no deal given".to_string());
+    }
+    if options.budget.iterations == 0 {
+        return Err("the iteration count must be positive".to_string());
+    }
+    if options.budget.weight == 0 {
+        return Err("the weight must be positive".to_string());
+    }
+    Ok(options)
+}
+
+fn load_deal(deal: &str, variant: Variant) -> Result<Vec<Stack>, String> {
+    if Path::new(deal).is_file() {
+        let text = fs::read_to_string(deal).map_err(|e| format!("{}: {}", deal, e))?;
+        let stacks = notation::parse_board(&text)
+            .or_else(|e| {
+                savegame::parse_board(&text)
+                    .filter(|stacks| !stacks.is_empty())
+                    .ok_or(e)
+            })
+            .map_err(|e| format!("{}: {}", deal, e))?;
+        if stacks.is_empty() {
+            return Err(format!("{}: no stacks", deal));
+        }
+        return Ok(stacks);
+    }
+
+    let seed = u64::from_str_radix(deal.trim_start_matches("0x"), 16)
+        .map_err(|_| format!("{}: neither a file nor a seed", deal))?;
+    Ok(solitaire_core::deal::deal(seed, variant))
+}
+
+/// Human readable name of a stack, e.g. "column 3" or "foundation 1".
+fn stack_name(stacks: &[Stack], i: usize) -> String {
+    let role = stacks[i].role;
+    let nr = stacks[..i].iter().filter(|s| s.role == role).count() + 1;
+    match role {
+        StackRole::Sorting => format!("column {}", nr),
+        StackRole::Dragon => format!("free cell {}", nr),
+        StackRole::Flower => "flower".to_string(),
+        StackRole::Target => format!("foundation {}", nr),
+        StackRole::Generic | StackRole::Animation => format!("stack {}", i),
+    }
+}
+
+fn card_name(card: Suite) -> String {
+    match card {
+        Suite::FaceDown => "face down card".to_string(),
+        Suite::Flower => "flower".to_string(),
+        Suite::Dragon(c) => format!("{:?} dragon", c),
+        Suite::Number(n, c) => format!("{:?} {}", c, n),
+    }
+}
+
+fn describe(stacks: &[Stack], m: Move) -> String {
+    match m {
+        Move::Cards(t, s, n) => {
+            let card = stacks[s].peek(stacks[s].len() - n);
+            let run = if n > 1 {
+                format!(" (+{} more)", n - 1)
+            } else {
+                String::new()
+            };
+            format!(
+                "{}{}: {} -> {}",
+                card_name(card),
+                run,
+                stack_name(stacks, s),
+                stack_name(stacks, t)
+            )
+        }
+        Move::Button(c, t, _) => format!("collapse {:?} dragons -> {}", c, stack_name(stacks, t)),
+    }
+}
+
+fn main() {
+    let options = match parse_args() {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("error: {}\n\n{}", e, USAGE);
+            process::exit(2);
+        }
+    };
+
+    // a deal that could not be loaded or whose solution does not work
+    let mut failed = false;
+    for deal in &options.deals {
+        let stacks = match load_deal(deal, options.variant) {
+            Ok(stacks) => stacks,
+            Err(e) => {
+                eprintln!("error: {}", e);
+                failed = true;
+                continue;
+            }
+        };
+
+        if options.print {
+            print!("# deal {}\n{}", deal, notation::write_board(&stacks));
+        }
+
+        let ai = match AiState::new(&stacks) {
+            Some(ai) => ai.with_policy(options.policy).with_variant(options.variant),
+            None => {
+                eprintln!("error: {}: too many stacks or cards", deal);
+                failed = true;
+                continue;
+            }
+        };
+
+        let start = Instant::now();
+        let (result, stats) = ai.search(options.budget);
+        let secs = start.elapsed().as_secs_f64();
+        if options.stats {
+            println!(
+                "{}: expanded {} states, visited {}, {} dead ends",
+                deal, stats.expanded, stats.visited, stats.dead_ends
+            );
+        }
+
+        match result {
+            AiResult::Unknown => println!("{}: unknown ({:.2}s)", deal, secs),
+            AiResult::Lost => println!("{}: lost ({:.2}s)", deal, secs),
+            AiResult::Winable(moves) => {
+                let mut board = Board::new(stacks.clone())
+                    .with_policy(options.policy)
+                    .with_variant(options.variant);
+                if !rules::is_solution(&board, &moves) {
+                    eprintln!("error: {}: the solver returned an invalid solution", deal);
+                    failed = true;
+                    continue;
+                }
+                println!(
+                    "{}: winnable in {} moves ({:.2}s), rated {}",
+                    deal,
+                    moves.len(),
+                    secs,
+                    ai.difficulty(&moves, &stats)
+                );
+                if options.quiet {
+                    continue;
+                }
+                for (i, &m) in moves.iter().enumerate() {
+                    println!("{:4}. {}", i + 1, describe(board.stacks(), m));
+                    if let Err(e) = board.apply_move(m) {
+                        eprintln!("error: {}: move {}: {}", deal, i + 1, e);
+                        failed = true;
+                        break;
+                    }
+                }
+            }
+        }
+    }
+    if failed {
+        process::exit(1);
+    }
+}
@@ -0,0 +1,257 @@
+use deal;
+use rules::{self, AutomovePolicy, Move, MoveError};
+use types::*;
+use variant::Variant;
+
+/// The stacks on the table, changed only by legal moves.
+///
+/// Moves refer to stacks by their index on the board, which is the order of `Variant::table` for
+/// a fresh deal. Every move made through `apply_move` can be taken back with `undo_move`, last
+/// move first.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct Board {
+    stacks: Vec<Stack>,
+    policy: AutomovePolicy,
+    variant: Variant,
+}
+
+impl Board {
+    /// A board with the safe automove policy and the standard rules.
+    pub fn new(stacks: Vec<Stack>) -> Board {
+        Board {
+            stacks,
+            policy: AutomovePolicy::Safe,
+            variant: Variant::Standard,
+        }
+    }
+
+    pub fn with_policy(mut self, policy: AutomovePolicy) -> Board {
+        self.policy = policy;
+        self
+    }
+
+    pub fn policy(&self) -> AutomovePolicy {
+        self.policy
+    }
+
+    pub fn with_variant(mut self, variant: Variant) -> Board {
+        self.variant = variant;
+        self
+    }
+
+    pub fn variant(&self) -> Variant {
+        self.variant
+    }
+
+    /// The table right after dealing the cards of a seed, with the rules of `variant`.
+    pub fn deal(seed: u64, variant: Variant) -> Board {
+        Board::new(deal::deal(seed, variant)).with_variant(variant)
+    }
+
+    pub fn stacks(&self) -> &[Stack] {
+        &self.stacks
+    }
+
+    pub fn into_stacks(self) -> Vec<Stack> {
+        self.stacks
+    }
+
+    pub fn is_won(&self) -> bool {
+        rules::check_victory(self.stacks.iter())
+    }
+
+    /// Check whether a move can be made, regardless of pending automatic moves.
+    pub fn check(&self, m: Move) -> Result<(), MoveError> {
+        rules::check_legal(&self.stacks, m, self.variant)
+    }
+
+    /// Every move a player can make. If a card is due to be moved automatically, that is the
+    /// only move.
+    pub fn possible_moves(&self) -> Vec<Move> {
+        rules::calc_possible_moves(self.stacks.iter(), self.policy, self.variant)
+    }
+
+    /// The next card that goes to the flower slot or a target stack on its own, according to the
+    /// automove policy of the board.
+    pub fn automove(&self) -> Option<Move> {
+        rules::get_automove(self.stacks.iter(), self.policy).map(|(t, s)| Move::Cards(t, s, 1))
+    }
+
+    /// The move that collapses the dragons of a color, if all four of them are free.
+    pub fn collapse_dragons(&self, color: Color) -> Option<Move> {
+        rules::check_button(color, self.stacks.iter(), self.variant)
+            .map(|(t, s)| Move::Button(color, t, s))
+    }
+
+    pub fn apply_move(&mut self, m: Move) -> Result<(), MoveError> {
+        self.check(m)?;
+        self.apply(m);
+        Ok(())
+    }
+
+    /// Make every automatic move that is due, and return them in order.
+    pub fn apply_automoves(&mut self) -> Vec<Move> {
+        let mut moves = Vec::new();
+        while let Some(m) = self.automove() {
+            self.apply(m);
+            moves.push(m);
+        }
+        moves
+    }
+
+    /// Make a move known to be legal, e.g. one of `possible_moves`.
+    pub(crate) fn apply(&mut self, m: Move) {
+        match m {
+            Move::Button(_, t, s) => {
+                for &i in &s {
+                    self.stacks[i].pop_card();
+                }
+                for _ in 0..4 {
+                    self.stacks[t].push_card(Suite::FaceDown);
+                }
+            }
+            Move::Cards(t, s, n) => {
+                let i = self.stacks[s].len() - n;
+                let tmp = self.stacks[s].split(i);
+                self.stacks[t].extend(tmp);
+            }
+        }
+    }
+
+    /// Take back a move. It has to be the last move applied to the board, otherwise the result
+    /// is a board that can not come up in a game.
+    pub fn undo_move(&mut self, m: Move) -> Result<(), MoveError> {
+        match m {
+            Move::Button(color, t, s) => {
+                let target = self.stacks.get(t).ok_or(MoveError::NoSuchStack)?;
+                if s.iter().any(|&i| i >= self.stacks.len()) {
+                    return Err(MoveError::NoSuchStack);
+                }
+                if target.len() < 4 || target.iter().rev().take(4).any(|&c| c != Suite::FaceDown) {
+                    return Err(MoveError::NoCards);
+                }
+
+                let at = target.len() - 4;
+                self.stacks[t].split(at);
+                for &i in &s {
+                    self.stacks[i].push_card(Suite::Dragon(color));
+                }
+            }
+            Move::Cards(t, s, n) => {
+                if s >= self.stacks.len() || t >= self.stacks.len() {
+                    return Err(MoveError::NoSuchStack);
+                }
+                if s == t {
+                    return Err(MoveError::SameStack);
+                }
+                if n == 0 || n > self.stacks[t].len() {
+                    return Err(MoveError::NoCards);
+                }
+
+                let at = self.stacks[t].len() - n;
+                let tmp = self.stacks[t].split(at);
+                self.stacks[s].extend(tmp);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ai;
+
+    #[test]
+    fn apply_and_undo() {
+        let start = Board::deal(5, Variant::Standard);
+        let moves = ai::solve_deal(5);
+
+        let mut board = start.clone();
+        let mut boards = Vec::new();
+        for &m in &moves {
+            boards.push(board.clone());
+            assert_eq!(board.apply_move(m), Ok(()));
+        }
+        assert!(board.is_won());
+
+        for &m in moves.iter().rev() {
+            assert_eq!(board.undo_move(m), Ok(()));
+            assert_eq!(Some(&board), boards.last());
+            boards.pop();
+        }
+        assert_eq!(board, start);
+    }
+
+    #[test]
+    fn automoves_and_errors() {
+        use types::Color::*;
+        use types::Suite::*;
+
+        let mut stacks = Variant::Standard.empty_table();
+        stacks[0].push_card(Number(1, Red));
+        stacks[1].push_card(Number(2, Red));
+        stacks[1].push_card(Number(1, Green));
+        stacks[2].push_card(Flower);
+        let mut board = Board::new(stacks);
+
+        assert_eq!(
+            board.apply_automoves(),
+            vec![
+                Move::Cards(11, 2, 1),
+                Move::Cards(12, 0, 1),
+                Move::Cards(13, 1, 1)
+            ]
+        );
+        assert_eq!(board.automove(), None);
+
+        let before = board.clone();
+        assert_eq!(
+            board.apply_move(Move::Cards(0, 0, 1)),
+            Err(MoveError::SameStack)
+        );
+        assert_eq!(
+            board.apply_move(Move::Cards(12, 20, 1)),
+            Err(MoveError::NoSuchStack)
+        );
+        assert_eq!(
+            board.apply_move(Move::Cards(13, 1, 1)),
+            Err(MoveError::WrongColor)
+        );
+        assert_eq!(
+            board.undo_move(Move::Cards(8, 0, 1)),
+            Err(MoveError::NoCards)
+        );
+        assert_eq!(
+            board.undo_move(Move::Button(White, 9, [0, 1, 2, 3])),
+            Err(MoveError::NoCards)
+        );
+        assert_eq!(board, before);
+    }
+
+    #[test]
+    fn collapse_to_sorting() {
+        use types::Color::*;
+        use types::Suite::*;
+
+        let mut stacks = Variant::TwoCells.empty_table();
+        for stack in &mut stacks[..4] {
+            stack.push_card(Dragon(Red));
+        }
+        stacks[8].push_card(Dragon(Green));
+        stacks[9].push_card(Dragon(White));
+
+        let standard = Board::new(stacks.clone());
+        assert_eq!(standard.collapse_dragons(Red), None);
+
+        let mut board = Board::new(stacks).with_variant(Variant::TwoCells);
+        let m = Move::Button(Red, 0, [0, 1, 2, 3]);
+        assert_eq!(board.collapse_dragons(Red), Some(m));
+        assert_eq!(board.apply_move(m), Ok(()));
+        assert_eq!(board.stacks()[0].cards, vec![FaceDown; 4]);
+        assert_eq!(
+            board.apply_move(Move::Cards(4, 0, 1)),
+            Err(MoveError::DragonSlotLocked)
+        );
+    }
+}
@@ -0,0 +1,96 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use rand::{seq::SliceRandom, thread_rng, Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use ai::{AiState, Budget, Difficulty};
+use types::*;
+use variant::Variant;
+
+/// Draw a fresh seed for a random deal.
+pub fn random_seed() -> u64 {
+    thread_rng().gen()
+}
+
+/// Draw random seeds until the solver proves one of them winnable within `budget` under the
+/// rules of `variant`, and of the `wanted` difficulty if one is given.
+///
+/// Gives up and returns `None` once `cancel` is set, which is checked before every deal.
+pub fn find_seed(
+    budget: Budget,
+    wanted: Option<Difficulty>,
+    variant: Variant,
+    cancel: &AtomicBool,
+) -> Option<(u64, Difficulty)> {
+    while !cancel.load(Ordering::Relaxed) {
+        let seed = random_seed();
+        let ai = AiState::new(&deal(seed, variant)).map(|ai| ai.with_variant(variant));
+        match ai.and_then(|ai| ai.rate(budget)) {
+            Some(d) if wanted.is_none() || wanted == Some(d) => return Some((seed, d)),
+            Some(d) => debug!("Deal {:016x} is {}", seed, d),
+            None => debug!("Deal {:016x} is not known to be winnable", seed),
+        }
+    }
+    None
+}
+
+/// The deck of `variant` shuffled by `seed`. The generator is fixed, so a seed deals the same
+/// cards on every platform.
+pub fn shuffled_deck(seed: u64, variant: Variant) -> Stack {
+    let mut cards = variant.deck();
+    cards.shuffle(&mut ChaCha8Rng::seed_from_u64(seed));
+
+    Stack {
+        cards,
+        role: StackRole::Generic,
+    }
+}
+
+/// The table right after dealing, before any automatic moves.
+///
+/// Cards are taken from the top of the deck and dealt round-robin onto the sorting stacks, the
+/// same way `Game::animate_deal` does it.
+pub fn deal(seed: u64, variant: Variant) -> Vec<Stack> {
+    let mut stacks = variant.empty_table();
+    let n_sorting = stacks
+        .iter()
+        .filter(|s| s.role == StackRole::Sorting)
+        .count();
+
+    let mut deck = shuffled_deck(seed, variant);
+    let mut s = 0;
+    while let Some(card) = deck.pop_card() {
+        stacks[s].push_card(card);
+        s = (s + 1) % n_sorting;
+    }
+
+    stacks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reproducible() {
+        use types::Color::*;
+        use types::Suite::*;
+
+        // the first card of each sorting stack, a new generator would deal other games
+        let stacks = deal(5, Variant::Standard);
+        let cards: Vec<_> = stacks[..8].iter().map(|s| s.cards[0]).collect();
+        assert_eq!(
+            cards,
+            vec![
+                Dragon(White),
+                Number(3, Green),
+                Number(7, Green),
+                Dragon(Red),
+                Number(9, Green),
+                Number(6, White),
+                Number(1, White),
+                Number(9, Red),
+            ]
+        );
+    }
+}
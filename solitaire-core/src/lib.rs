@@ -0,0 +1,16 @@
+//! The rules of the game, deals and the solver, without any graphics or sound.
+
+#[macro_use]
+extern crate log;
+extern crate rand;
+extern crate rand_chacha;
+
+pub mod ai;
+pub mod board;
+pub mod deal;
+pub mod notation;
+pub mod record;
+pub mod rules;
+pub mod savegame;
+pub mod types;
+pub mod variant;
@@ -0,0 +1,376 @@
+//! Plain text notation for cards, stacks and whole boards.
+//!
+//! A board has one stack per line, its role followed by its cards from bottom to top:
+//!
+//! ```text
+//! # deal 5a3c9e1f07b2d468
+//! sorting R5 G4 W3
+//! sorting Dr F
+//! dragon X X X X
+//! dragon
+//! flower
+//! target R1 R2
+//! ```
+//!
+//! Number cards are the color and the number (`R5`), dragons are `D` and the color (`Dr`), `F` is
+//! the flower and `X` a face down card, i.e. a collapsed dragon. Empty lines and everything after
+//! a `#` are ignored.
+//!
+//! Moves refer to stacks by their index on the board: `3>5x2` moves two cards from stack 3 to
+//! stack 5, and `DR` collapses the red dragons. Written in full, a collapse also names the stack
+//! the dragons go to and the four they come from, `DR>8:0,1,2,8`, so it can be read without the
+//! board.
+
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+use board::Board;
+use rules::Move;
+use types::*;
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ParseError {
+    UnknownCard(String),
+    UnknownRole(String),
+    MissingRole,
+    UnknownMove(String),
+    IllegalMove(String),
+    InvalidSeed(String),
+    MissingSeed,
+    UnknownVariant(String),
+    UnknownKeyword(String),
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ParseError::UnknownCard(ref s) => write!(f, "unknown card `{}`", s),
+            ParseError::UnknownRole(ref s) => write!(
+                f,
+                "unknown stack role `{}`, expected sorting, dragon, flower or target",
+                s
+            ),
+            ParseError::MissingRole => write!(f, "missing stack role"),
+            ParseError::UnknownMove(ref s) => write!(f, "unknown move `{}`", s),
+            ParseError::IllegalMove(ref s) => write!(f, "move `{}` is not possible here", s),
+            ParseError::InvalidSeed(ref s) => write!(f, "invalid seed `{}`", s),
+            ParseError::MissingSeed => write!(f, "missing seed"),
+            ParseError::UnknownVariant(ref s) => write!(f, "unknown variant `{}`", s),
+            ParseError::UnknownKeyword(ref s) => write!(f, "unknown keyword `{}`", s),
+        }
+    }
+}
+
+impl Error for ParseError {}
+
+/// A `ParseError` along with the line it occurred in, counting from 1.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct LineError {
+    pub line: usize,
+    pub error: ParseError,
+}
+
+impl fmt::Display for LineError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.error)
+    }
+}
+
+impl Error for LineError {}
+
+fn color_char(color: Color) -> char {
+    match color {
+        Color::Red => 'r',
+        Color::Green => 'g',
+        Color::White => 'w',
+    }
+}
+
+fn parse_color(c: char) -> Option<Color> {
+    match c.to_ascii_lowercase() {
+        'r' => Some(Color::Red),
+        'g' => Some(Color::Green),
+        'w' => Some(Color::White),
+        _ => None,
+    }
+}
+
+impl fmt::Display for Suite {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Suite::FaceDown => write!(f, "X"),
+            Suite::Flower => write!(f, "F"),
+            Suite::Dragon(c) => write!(f, "D{}", color_char(c)),
+            Suite::Number(n, c) => write!(f, "{}{}", color_char(c).to_ascii_uppercase(), n),
+        }
+    }
+}
+
+impl FromStr for Suite {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Suite, ParseError> {
+        let unknown = || ParseError::UnknownCard(s.to_string());
+        let mut chars = s.chars();
+        let card = match (chars.next().ok_or_else(unknown)?, chars.next()) {
+            ('X', None) => Suite::FaceDown,
+            ('F', None) => Suite::Flower,
+            ('D', Some(c)) => Suite::Dragon(parse_color(c).ok_or_else(unknown)?),
+            (c, Some(n)) => match n.to_digit(10) {
+                Some(n @ 1..=9) => Suite::Number(n as u8, parse_color(c).ok_or_else(unknown)?),
+                _ => return Err(unknown()),
+            },
+            _ => return Err(unknown()),
+        };
+        match chars.next() {
+            None => Ok(card),
+            Some(_) => Err(unknown()),
+        }
+    }
+}
+
+impl fmt::Display for StackRole {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match *self {
+            StackRole::Dragon => "dragon",
+            StackRole::Flower => "flower",
+            StackRole::Target => "target",
+            StackRole::Sorting => "sorting",
+            StackRole::Generic => "generic",
+            StackRole::Animation => "animation",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Only the roles of stacks on the table can be parsed.
+impl FromStr for StackRole {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<StackRole, ParseError> {
+        match s {
+            "dragon" => Ok(StackRole::Dragon),
+            "flower" => Ok(StackRole::Flower),
+            "target" => Ok(StackRole::Target),
+            "sorting" => Ok(StackRole::Sorting),
+            _ => Err(ParseError::UnknownRole(s.to_string())),
+        }
+    }
+}
+
+impl fmt::Display for Stack {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.role)?;
+        for card in self.iter() {
+            write!(f, " {}", card)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Stack {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Stack, ParseError> {
+        let mut tokens = s.split_whitespace();
+        let role = tokens.next().ok_or(ParseError::MissingRole)?.parse()?;
+        let mut stack = Stack::new(role);
+        for t in tokens {
+            stack.push_card(t.parse()?);
+        }
+        Ok(stack)
+    }
+}
+
+impl fmt::Display for Move {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Move::Cards(t, s, n) => write!(f, "{}>{}x{}", s, t, n),
+            Move::Button(c, _, _) => write!(f, "D{}", color_char(c).to_ascii_uppercase()),
+        }
+    }
+}
+
+/// Read a move and check that it can be made on `board`.
+///
+/// A dragon collapse only names the color, where the dragons go and come from is taken from the
+/// board.
+pub fn parse_move(s: &str, board: &Board) -> Result<Move, ParseError> {
+    let unknown = || ParseError::UnknownMove(s.to_string());
+    let m = if s.starts_with('D') {
+        let color = match split_collapse(s) {
+            Some((color, "")) => color,
+            _ => return Err(unknown()),
+        };
+        match board.collapse_dragons(color) {
+            Some(m) => m,
+            None => return Err(ParseError::IllegalMove(s.to_string())),
+        }
+    } else {
+        parse_cards(s).ok_or_else(unknown)?
+    };
+
+    if board.check(m).is_ok() {
+        Ok(m)
+    } else {
+        Err(ParseError::IllegalMove(s.to_string()))
+    }
+}
+
+/// Write a move in full, so that `parse_full_move` can read it without a board.
+pub fn write_full_move(m: Move) -> String {
+    match m {
+        Move::Cards(..) => m.to_string(),
+        Move::Button(_, t, s) => format!("{}>{}:{},{},{},{}", m, t, s[0], s[1], s[2], s[3]),
+    }
+}
+
+/// Read a move written by `write_full_move`. Unlike `parse_move` it is not checked against a
+/// board.
+pub fn parse_full_move(s: &str) -> Result<Move, ParseError> {
+    let m = if s.starts_with('D') {
+        parse_button(s)
+    } else {
+        parse_cards(s)
+    };
+    m.ok_or_else(|| ParseError::UnknownMove(s.to_string()))
+}
+
+fn parse_cards(s: &str) -> Option<Move> {
+    let mut parts = s.split(&['>', 'x'][..]).map(|p| p.parse::<usize>());
+    match (parts.next(), parts.next(), parts.next(), parts.next()) {
+        (Some(Ok(src)), Some(Ok(dst)), Some(Ok(n)), None) => Some(Move::Cards(dst, src, n)),
+        _ => None,
+    }
+}
+
+fn parse_button(s: &str) -> Option<Move> {
+    let (color, rest) = split_collapse(s)?;
+    let mut parts = rest.strip_prefix('>')?.split(':');
+    let t = parts.next()?.parse().ok()?;
+    let mut sources = [0; 4];
+    let mut it = parts.next()?.split(',');
+    for source in sources.iter_mut() {
+        *source = it.next()?.parse().ok()?;
+    }
+    if parts.next().is_some() || it.next().is_some() {
+        return None;
+    }
+    Some(Move::Button(color, t, sources))
+}
+
+/// The color of a dragon collapse and what follows it.
+fn split_collapse(s: &str) -> Option<(Color, &str)> {
+    let mut chars = s.strip_prefix('D')?.chars();
+    let color = chars.next().and_then(parse_color)?;
+    Some((color, chars.as_str()))
+}
+
+/// Write a board, one stack per line.
+pub fn write_board(stacks: &[Stack]) -> String {
+    let mut s = String::new();
+    for stack in stacks {
+        s.push_str(&stack.to_string());
+        s.push('\n');
+    }
+    s
+}
+
+pub fn parse_board(s: &str) -> Result<Vec<Stack>, LineError> {
+    let mut stacks = Vec::new();
+    for (i, line) in s.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or("");
+        if line.trim().is_empty() {
+            continue;
+        }
+        let stack = line
+            .parse()
+            .map_err(|error| LineError { line: i + 1, error })?;
+        stacks.push(stack);
+    }
+    Ok(stacks)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use deal;
+    use variant::Variant;
+
+    #[test]
+    fn cards() {
+        use self::Color::*;
+        use self::Suite::*;
+
+        let mut cards = vec![FaceDown, Flower];
+        for &c in &[Red, Green, White] {
+            cards.push(Dragon(c));
+            for n in 1..10 {
+                cards.push(Number(n, c));
+            }
+        }
+        for card in cards {
+            assert_eq!(card.to_string().parse(), Ok(card));
+        }
+
+        assert_eq!("Dr".parse(), Ok(Dragon(Red)));
+        assert_eq!("g7".parse(), Ok(Number(7, Green)));
+        for &bad in &["", "R0", "R10", "Dx", "Q5", "XX", "W"] {
+            assert_eq!(
+                bad.parse::<Suite>(),
+                Err(ParseError::UnknownCard(bad.to_string()))
+            );
+        }
+    }
+
+    #[test]
+    fn roundtrip() {
+        let stacks = deal::deal(0x5a3c_9e1f_07b2_d468, Variant::Standard);
+        assert_eq!(parse_board(&write_board(&stacks)), Ok(stacks));
+    }
+
+    #[test]
+    fn full_moves() {
+        let moves = [
+            Move::Cards(5, 3, 2),
+            Move::Button(Color::Green, 8, [0, 1, 2, 8]),
+        ];
+        for &m in &moves {
+            assert_eq!(parse_full_move(&write_full_move(m)), Ok(m));
+        }
+        assert_eq!(write_full_move(moves[1]), "DG>8:0,1,2,8");
+        assert_eq!(parse_full_move("Dg>8:0,1,2,8"), Ok(moves[1]));
+        for &bad in &[
+            "DG",
+            "DG>8",
+            "DG>8:0,1,2",
+            "DG>8:0,1,2,8,9",
+            "DG>8:0,1,2,8:9",
+            "3>5",
+        ] {
+            assert_eq!(
+                parse_full_move(bad),
+                Err(ParseError::UnknownMove(bad.to_string()))
+            );
+        }
+    }
+
+    #[test]
+    fn errors() {
+        let board = "# a comment\nsorting R1 G2\n\n  flower F # the flower\n";
+        assert_eq!(parse_board(board).unwrap().len(), 2);
+
+        assert_eq!(
+            parse_board("sorting R1\nsorting R1 G\n"),
+            Err(LineError {
+                line: 2,
+                error: ParseError::UnknownCard("G".to_string()),
+            })
+        );
+        assert_eq!(
+            parse_board("freecell Dr").unwrap_err().to_string(),
+            "line 1: unknown stack role `freecell`, expected sorting, dragon, flower or target"
+        );
+    }
+}
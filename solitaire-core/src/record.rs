@@ -0,0 +1,193 @@
+use std::fmt;
+
+use board::Board;
+use notation::{self, LineError, ParseError};
+use rules::{AutomovePolicy, Move};
+use variant::Variant;
+
+/// Moves per line when writing a record.
+const LINE_LENGTH: usize = 10;
+
+/// A finished game: the deal and every move made, including the automatic ones.
+///
+/// ```text
+/// seed 5a3c9e1f07b2d468
+/// variant two-cells
+/// moves 3>5x2 0>12x1 DR 7>9x1
+/// ```
+///
+/// Moves are written in the notation of the `notation` module, over as many `moves` lines as
+/// needed. Stacks are numbered in the order of `Variant::table`. The `variant` line is left out
+/// for the standard rules.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Record {
+    pub seed: u64,
+    pub variant: Variant,
+    pub moves: Vec<Move>,
+}
+
+impl Record {
+    pub fn new(seed: u64, variant: Variant, moves: Vec<Move>) -> Record {
+        Record {
+            seed,
+            variant,
+            moves,
+        }
+    }
+
+    /// The record of a game played with automatic moves, where `moves` starts with the first
+    /// move of the player. The cards that went to the targets on their own right after the deal
+    /// are put in front.
+    pub fn from_game(
+        seed: u64,
+        variant: Variant,
+        policy: AutomovePolicy,
+        moves: Vec<Move>,
+    ) -> Record {
+        let mut board = Board::deal(seed, variant).with_policy(policy);
+        let mut all = board.apply_automoves();
+        all.extend(moves);
+        Record::new(seed, variant, all)
+    }
+
+    /// The table after all moves of the record are made. Stops at the first illegal move.
+    pub fn board(&self) -> Board {
+        let mut board = Board::deal(self.seed, self.variant);
+        for &m in &self.moves {
+            if board.apply_move(m).is_err() {
+                break;
+            }
+        }
+        board
+    }
+
+    pub fn is_won(&self) -> bool {
+        self.board().is_won()
+    }
+
+    /// Read a record. Every move is checked against the table it is made on.
+    pub fn parse(s: &str) -> Result<Record, LineError> {
+        let mut seed = None;
+        let mut variant = Variant::Standard;
+        let mut moves = Vec::new();
+
+        for (i, line) in s.lines().enumerate() {
+            let error = |error| LineError { line: i + 1, error };
+            let mut tokens = line.split_whitespace();
+            match (tokens.next(), seed) {
+                (None, _) => continue,
+                (Some("seed"), None) => {
+                    let t = tokens.next().unwrap_or("");
+                    let s = u64::from_str_radix(t, 16)
+                        .map_err(|_| error(ParseError::InvalidSeed(t.to_string())))?;
+                    seed = Some(s);
+                }
+                (Some("variant"), _) => {
+                    let t = tokens.next().unwrap_or("");
+                    variant = t
+                        .parse()
+                        .map_err(|_| error(ParseError::UnknownVariant(t.to_string())))?;
+                }
+                (Some("moves"), Some(_)) => moves.extend(tokens.map(|t| (i + 1, t))),
+                (Some("moves"), None) => return Err(error(ParseError::MissingSeed)),
+                (Some(keyword), _) => {
+                    return Err(error(ParseError::UnknownKeyword(keyword.to_string())))
+                }
+            }
+        }
+
+        let seed = seed.ok_or(LineError {
+            line: 1,
+            error: ParseError::MissingSeed,
+        })?;
+
+        // the variant decides the table, so the moves are only checked once it is known
+        let mut record = Record::new(seed, variant, Vec::new());
+        let mut board = Board::deal(seed, variant);
+        for (line, t) in moves {
+            let error = |error| LineError { line, error };
+            let m = notation::parse_move(t, &board).map_err(error)?;
+            board
+                .apply_move(m)
+                .map_err(|_| error(ParseError::IllegalMove(t.to_string())))?;
+            record.moves.push(m);
+        }
+        Ok(record)
+    }
+}
+
+impl fmt::Display for Record {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "seed {:016x}", self.seed)?;
+        if self.variant != Variant::Standard {
+            writeln!(f, "variant {}", self.variant)?;
+        }
+        for line in self.moves.chunks(LINE_LENGTH) {
+            write!(f, "moves")?;
+            for m in line {
+                write!(f, " {}", m)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ai;
+
+    #[test]
+    fn roundtrip() {
+        let moves = ai::solve_deal(5);
+        assert!(moves.iter().any(|m| matches!(m, Move::Button(..))));
+
+        let record = Record::new(5, Variant::Standard, moves);
+        let text = record.to_string();
+        assert!(text.contains(" DR") || text.contains(" DG") || text.contains(" DW"));
+        assert_eq!(Record::parse(&text), Ok(record.clone()));
+        assert!(record.is_won());
+    }
+
+    #[test]
+    fn opening_automoves() {
+        let seed = (0..100)
+            .find(|&seed| Board::deal(seed, Variant::Standard).automove().is_some())
+            .unwrap();
+        let mut board = Board::deal(seed, Variant::Standard);
+        let opening = board.apply_automoves();
+        let first = board.possible_moves()[0];
+
+        let record = Record::from_game(seed, Variant::Standard, AutomovePolicy::Safe, vec![first]);
+        assert_eq!(record.moves.len(), opening.len() + 1);
+        assert_eq!(record.moves[..opening.len()], opening[..]);
+        assert_eq!(Record::parse(&record.to_string()), Ok(record));
+    }
+
+    #[test]
+    fn errors() {
+        let error = |line, error| Err(LineError { line, error });
+        assert_eq!(Record::parse(""), error(1, ParseError::MissingSeed));
+        assert_eq!(
+            Record::parse("moves 0>8x1"),
+            error(1, ParseError::MissingSeed)
+        );
+        assert_eq!(
+            Record::parse("seed 5\nmoves 0>8x1 0>9x2"),
+            error(2, ParseError::IllegalMove("0>9x2".to_string()))
+        );
+        assert_eq!(
+            Record::parse("seed 5\n\nmoves DR"),
+            error(3, ParseError::IllegalMove("DR".to_string()))
+        );
+        assert_eq!(
+            Record::parse("seed 5\nmoves 0-8"),
+            error(2, ParseError::UnknownMove("0-8".to_string()))
+        );
+        assert_eq!(
+            Record::parse("seed 5\nvariant four-suits"),
+            error(2, ParseError::UnknownVariant("four-suits".to_string()))
+        );
+    }
+}
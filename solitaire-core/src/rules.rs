@@ -0,0 +1,578 @@
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+use board::Board;
+use types::*;
+use variant::Variant;
+
+pub fn check_victory<'a, I: Iterator<Item = &'a Stack> + Clone>(stacks: I) -> bool {
+    // sorting stacks may hold collapsed dragons in some variants
+    let a = stacks
+        .clone()
+        .filter(|s| s.role == StackRole::Sorting)
+        .all(|s| s.iter().all(|&c| c == Suite::FaceDown));
+
+    let b = stacks
+        .clone()
+        .filter(|s| s.role == StackRole::Target)
+        .all(|s| s.len() == 9);
+
+    a && b
+}
+
+pub fn is_valid_pair(lower: Suite, upper: Suite) -> bool {
+    use self::Suite::*;
+    match (lower, upper) {
+        (Number(ln, lc), Number(un, uc)) => lc != uc && ln == un + 1,
+        _ => false,
+    }
+}
+
+pub fn is_valid_sequence<'a, T: Iterator<Item = &'a Suite>>(cards: T) -> bool {
+    let mut iter = cards.into_iter();
+    let mut lower = match iter.next() {
+        None => return true, // an empty sequence is a valid sequence
+        Some(&l) => l,
+    };
+    for &upper in iter {
+        if !is_valid_pair(lower, upper) {
+            return false;
+        }
+        lower = upper;
+    }
+    true
+}
+
+/// Why a move is not allowed.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum MoveError {
+    /// A stack index that is not on the table.
+    NoSuchStack,
+    /// Source and target are the same stack.
+    SameStack,
+    /// There are no cards to pick up.
+    NoCards,
+    /// Stacks that only exist while cards are dragged or animated take no part in moves.
+    InvalidStack,
+    /// Cards on the flower slot and the foundations stay there.
+    NotMovable,
+    /// The cards to pick up do not form a sequence of alternating colors.
+    NotASequence,
+    /// Free cells, the flower slot and the foundations take one card at a time.
+    TooManyCards,
+    /// The free cell or the flower slot already holds a card.
+    SlotOccupied,
+    /// The free cell or sorting stack holds collapsed dragons.
+    DragonSlotLocked,
+    /// Only the flower goes to the flower slot.
+    FlowerOnly,
+    /// Only number cards can be sorted and put on the foundations.
+    NotANumber,
+    /// Sorted cards alternate in color, foundations take a single color.
+    WrongColor,
+    /// A card on a sorting stack has to be one lower than the card below it.
+    NotOneLower,
+    /// A card on a foundation has to be one higher than the card below it.
+    NotOneHigher,
+    /// Not all four dragons of the color are free, or no free cell can take them.
+    DragonsNotFree,
+}
+
+impl fmt::Display for MoveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let msg = match *self {
+            MoveError::NoSuchStack => "There is no such stack.",
+            MoveError::SameStack => "The cards are already there.",
+            MoveError::NoCards => "There are no cards to move.",
+            MoveError::InvalidStack => "These cards are not on the table.",
+            MoveError::NotMovable => "Cards on the flower slot and the foundations stay there.",
+            MoveError::NotASequence => "Only sorted cards can be moved together.",
+            MoveError::TooManyCards => "Only one card fits here.",
+            MoveError::SlotOccupied => "This free cell is taken.",
+            MoveError::DragonSlotLocked => "This place is locked by the dragons.",
+            MoveError::FlowerOnly => "Only the flower goes here.",
+            MoveError::NotANumber => "Only number cards can be sorted.",
+            MoveError::WrongColor => "The card has the wrong color.",
+            MoveError::NotOneLower => "The card has to be one lower.",
+            MoveError::NotOneHigher => "The card has to be one higher.",
+            MoveError::DragonsNotFree => "The dragons can not be collapsed yet.",
+        };
+        write!(f, "{}", msg)
+    }
+}
+
+impl Error for MoveError {}
+
+/// Check whether the cards from `idx` to the top of `stack` can be picked up.
+pub fn check_drag(stack: &Stack, idx: usize) -> Result<(), MoveError> {
+    if idx >= stack.len() {
+        return Err(MoveError::NoCards);
+    }
+    match stack.role {
+        StackRole::Flower | StackRole::Target => Err(MoveError::NotMovable),
+        StackRole::Dragon if stack.peek(idx) == Suite::FaceDown => Err(MoveError::DragonSlotLocked),
+        StackRole::Dragon => Ok(()),
+        StackRole::Sorting if stack.peek(idx) == Suite::FaceDown => {
+            Err(MoveError::DragonSlotLocked)
+        }
+        StackRole::Sorting if is_valid_sequence(stack.cards[idx..].iter()) => Ok(()),
+        StackRole::Sorting => Err(MoveError::NotASequence),
+        StackRole::Generic | StackRole::Animation => Err(MoveError::InvalidStack),
+    }
+}
+
+/// Check whether the picked up cards of `source` can be put on `target`.
+pub fn check_drop(target: &Stack, source: &Stack, variant: Variant) -> Result<(), MoveError> {
+    match source.cards.first() {
+        Some(&base_card) => check_move(target, base_card, source.len(), variant),
+        None => Err(MoveError::NoCards),
+    }
+}
+
+/// Check whether `n_cards` cards, the lowest of which is `base_card`, can be put on `target`.
+pub fn check_move(
+    target: &Stack,
+    base_card: Suite,
+    n_cards: usize,
+    variant: Variant,
+) -> Result<(), MoveError> {
+    use self::Suite::*;
+
+    if n_cards == 0 {
+        return Err(MoveError::NoCards);
+    }
+    let one_card = |ok| {
+        if n_cards == 1 {
+            ok
+        } else {
+            Err(MoveError::TooManyCards)
+        }
+    };
+
+    match (target.role, target.top(), base_card) {
+        (StackRole::Dragon, None, _) => one_card(Ok(())),
+        (StackRole::Dragon, Some(FaceDown), _) => Err(MoveError::DragonSlotLocked),
+        (StackRole::Dragon, Some(_), _) => Err(MoveError::SlotOccupied),
+        (StackRole::Flower, Some(_), _) => Err(MoveError::SlotOccupied),
+        (StackRole::Flower, None, Flower) => one_card(Ok(())),
+        (StackRole::Flower, _, _) => Err(MoveError::FlowerOnly),
+        (StackRole::Target, None, Number(1, _)) => one_card(Ok(())),
+        (StackRole::Target, None, Number(..)) => one_card(Err(MoveError::NotOneHigher)),
+        (StackRole::Target, Some(Number(ln, lc)), Number(un, uc)) => one_card(if lc != uc {
+            Err(MoveError::WrongColor)
+        } else if ln + 1 != un {
+            Err(MoveError::NotOneHigher)
+        } else {
+            Ok(())
+        }),
+        (StackRole::Target, _, _) => Err(MoveError::NotANumber),
+        (StackRole::Sorting, None, _) => Ok(()),
+        (StackRole::Sorting, Some(FaceDown), _) => Err(MoveError::DragonSlotLocked),
+        (StackRole::Sorting, Some(_), Dragon(_)) if variant.dragons_anywhere() => one_card(Ok(())),
+        (StackRole::Sorting, Some(Number(ln, lc)), Number(un, uc)) => {
+            if lc == uc {
+                Err(MoveError::WrongColor)
+            } else if ln != un + 1 {
+                Err(MoveError::NotOneLower)
+            } else {
+                Ok(())
+            }
+        }
+        (StackRole::Sorting, Some(_), _) => Err(MoveError::NotANumber),
+        (StackRole::Generic, _, _) | (StackRole::Animation, _, _) => Err(MoveError::InvalidStack),
+    }
+}
+
+pub fn check_button<'a, I: Iterator<Item = &'a Stack> + Clone>(
+    color: Color,
+    stacks: I,
+    variant: Variant,
+) -> Option<(usize, [usize; 4])> {
+    // only dragons of right color, or an empty stack
+    let fits = |stack: &Stack| match stack.top() {
+        Some(Suite::Dragon(col)) => col == color,
+        None => true,
+        _ => false,
+    };
+    let cell = stacks
+        .clone()
+        .position(|stack| stack.role == StackRole::Dragon && fits(stack));
+    let column = || {
+        stacks
+            .clone()
+            .position(|stack| stack.role == StackRole::Sorting && stack.len() <= 1 && fits(stack))
+    };
+
+    let target = match cell {
+        Some(t) => t,
+        None if variant.dragons_to_sorting() => column()?,
+        None => return None,
+    };
+
+    let source_it = stacks
+        .enumerate()
+        .filter(|&(_, ref stack)| match stack.top() {
+            // only dragons of right color
+            Some(Suite::Dragon(col)) => color == col,
+            _ => false,
+        })
+        .map(|(i, _)| i);
+
+    // a board read from a file may have more than four dragons of a color
+    match *source_it.collect::<Vec<_>>() {
+        [a, b, c, d] => Some((target, [a, b, c, d])),
+        _ => None,
+    }
+}
+
+/// Which cards go to the flower slot and the target stacks without the player moving them.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub enum AutomovePolicy {
+    /// Every card has to be moved by hand, even the flower.
+    Off,
+    /// The flower, and number cards that no other card can be sorted onto any more: those at
+    /// most one above the lowest target stack.
+    #[default]
+    Safe,
+    /// The flower and every number card that fits on a target stack.
+    Aggressive,
+}
+
+impl AutomovePolicy {
+    pub const ALL: [AutomovePolicy; 3] = [
+        AutomovePolicy::Off,
+        AutomovePolicy::Safe,
+        AutomovePolicy::Aggressive,
+    ];
+}
+
+impl fmt::Display for AutomovePolicy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match *self {
+            AutomovePolicy::Off => "off",
+            AutomovePolicy::Safe => "safe",
+            AutomovePolicy::Aggressive => "aggressive",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for AutomovePolicy {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<AutomovePolicy, ()> {
+        AutomovePolicy::ALL
+            .iter()
+            .cloned()
+            .find(|p| p.to_string() == s)
+            .ok_or(())
+    }
+}
+
+pub fn get_automove<'a, I: Iterator<Item = &'a Stack> + Clone>(
+    stacks: I,
+    policy: AutomovePolicy,
+) -> Option<(usize, usize)> {
+    use self::Suite::*;
+
+    if policy == AutomovePolicy::Off {
+        return None;
+    }
+
+    let lowest_nr = stacks
+        .clone()
+        .filter_map(|s| match (s.role, s.top()) {
+            (StackRole::Target, None) => Some(0),
+            (StackRole::Target, Some(Number(n, _))) => Some(n),
+            _ => None,
+        })
+        .min()
+        .unwrap_or(0);
+
+    for (i, t_stack) in stacks.clone().enumerate() {
+        match t_stack.role {
+            StackRole::Target | StackRole::Flower => {}
+            _ => continue,
+        }
+
+        for (j, s_stack) in stacks.clone().enumerate() {
+            match s_stack.role {
+                StackRole::Dragon | StackRole::Sorting => {}
+                _ => continue,
+            }
+
+            match (s_stack.top(), t_stack.top()) {
+                //(Flower, None, StackRole::Flower) =>
+                (Some(Number(n, c)), _) => {
+                    let safe = n <= lowest_nr + 1 || policy == AutomovePolicy::Aggressive;
+                    if safe && check_move(t_stack, Number(n, c), 1, Variant::Standard).is_ok() {
+                        return Some((i, j));
+                    }
+                }
+                (Some(card), _) => {
+                    if check_move(t_stack, card, 1, Variant::Standard).is_ok() {
+                        return Some((i, j));
+                    }
+                }
+                _ => continue,
+            }
+        }
+    }
+    None
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Move {
+    Button(Color, usize, [usize; 4]),
+    Cards(usize, usize, usize),
+}
+
+/// Check whether a move can be made on the table, regardless of pending automatic moves.
+pub fn check_legal(stacks: &[Stack], m: Move, variant: Variant) -> Result<(), MoveError> {
+    match m {
+        Move::Cards(t, s, n) => {
+            let (source, target) = match (stacks.get(s), stacks.get(t)) {
+                (Some(source), Some(target)) => (source, target),
+                _ => return Err(MoveError::NoSuchStack),
+            };
+            if s == t {
+                return Err(MoveError::SameStack);
+            }
+            if n == 0 || n > source.len() {
+                return Err(MoveError::NoCards);
+            }
+            check_drag(source, source.len() - n)?;
+            check_move(target, source.peek(source.len() - n), n, variant)
+        }
+        Move::Button(c, t, sources) => {
+            if check_button(c, stacks.iter(), variant) == Some((t, sources)) {
+                Ok(())
+            } else {
+                Err(MoveError::DragonsNotFree)
+            }
+        }
+    }
+}
+
+/// Check that `moves` can be played one after another from `board` and win the game.
+pub fn is_solution(board: &Board, moves: &[Move]) -> bool {
+    let mut board = board.clone();
+    for &m in moves {
+        if !board.possible_moves().contains(&m) || board.apply_move(m).is_err() {
+            return false;
+        }
+    }
+    board.is_won()
+}
+
+/// Every move a player can make under the rules of `variant`. If a card is due to be moved
+/// automatically under `policy`, that is the only move.
+pub fn calc_possible_moves<'a, I: Iterator<Item = &'a Stack> + Clone>(
+    stacks: I,
+    policy: AutomovePolicy,
+    variant: Variant,
+) -> Vec<Move> {
+    let mut moves = Vec::new();
+
+    if let Some((t, s)) = get_automove(stacks.clone(), policy) {
+        moves.push(Move::Cards(t, s, 1));
+        return moves;
+    }
+
+    for &color in &[Color::Red, Color::Green, Color::White] {
+        if let Some((t, s)) = check_button(color, stacks.clone(), variant) {
+            moves.push(Move::Button(color, t, s));
+        }
+    }
+
+    for (s, s_stack) in stacks.clone().enumerate() {
+        for i in (0..s_stack.len()).rev() {
+            let n = s_stack.len() - i;
+            if check_drag(s_stack, i).is_err() {
+                break;
+            }
+            let card = s_stack.peek(i);
+            for (t, t_stack) in stacks.clone().enumerate() {
+                if check_move(t_stack, card, n, variant).is_ok() {
+                    moves.push(Move::Cards(t, s, n))
+                }
+            }
+        }
+    }
+
+    moves
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn valid_pairs() {
+        use self::Color::*;
+        use self::Suite::*;
+
+        for i in 1..9 {
+            assert!(is_valid_pair(Number(i + 1, Green), Number(i, Red)));
+            assert!(is_valid_pair(Number(i + 1, Green), Number(i, White)));
+            assert!(is_valid_pair(Number(i + 1, White), Number(i, Red)));
+            assert!(is_valid_pair(Number(i + 1, Red), Number(i, Green)));
+            assert!(is_valid_pair(Number(i + 1, White), Number(i, Green)));
+            assert!(is_valid_pair(Number(i + 1, Red), Number(i, White)));
+            assert!(!is_valid_pair(Number(i + 1, Red), Number(i, Red)));
+            assert!(!is_valid_pair(Number(i + 1, Green), Number(i, Green)));
+            assert!(!is_valid_pair(Number(i + 1, White), Number(i, White)));
+        }
+
+        for &c1 in &[Red, Green, White] {
+            for &c2 in &[Red, Green, White] {
+                for i in 1..10 {
+                    for j in 1..10 {
+                        if i == j + 1 {
+                            continue;
+                        }
+                        assert!(!is_valid_pair(Number(i, c1), Number(j, c2)));
+                    }
+                    assert!(!is_valid_pair(Number(i, c1), Dragon(c2)));
+                    assert!(!is_valid_pair(Number(i, c1), Flower));
+                    assert!(!is_valid_pair(Dragon(c1), Number(i, c2)));
+                    assert!(!is_valid_pair(Flower, Number(i, c2)));
+                }
+                assert!(!is_valid_pair(Dragon(c1), Dragon(c2)));
+            }
+            assert!(!is_valid_pair(Dragon(c1), Flower));
+            assert!(!is_valid_pair(Flower, Dragon(c1)));
+        }
+        assert!(!is_valid_pair(Flower, Flower));
+    }
+
+    #[test]
+    fn valid_sequences() {
+        use self::Color::*;
+        use self::Suite::*;
+        use std::iter;
+
+        let valid_vec = vec![Number(5, White), Number(4, Red), Number(3, White)];
+        let valid_slice = &[Number(9, Red), Number(8, Green), Number(7, White)];
+
+        let valid_iter = iter::once(&Number(3, Green))
+            .chain(iter::once(&Number(2, Red)))
+            .chain(iter::once(&Number(1, White)));
+
+        assert!(is_valid_sequence(valid_vec.iter()));
+        assert!(is_valid_sequence(valid_vec.iter()));
+        assert!(is_valid_sequence(Vec::new().iter())); // empty sequence
+
+        assert!(is_valid_sequence(valid_slice.iter()));
+        assert!(is_valid_sequence(valid_slice.iter()));
+        assert!(is_valid_sequence([].iter())); // empty sequence
+        assert!(is_valid_sequence([Number(42, Red)].iter())); // single item
+
+        assert!(is_valid_sequence(valid_iter));
+        assert!(is_valid_sequence(iter::empty())); // empty sequence
+        assert!(is_valid_sequence(iter::once(&Number(6, White)))); // single item
+
+        assert!(!is_valid_sequence(
+            [Number(3, Red), Number(2, Red), Number(1, Red)].iter()
+        ));
+        assert!(!is_valid_sequence(
+            [Number(3, Red), Number(2, Green), Dragon(White)].iter()
+        ));
+        assert!(!is_valid_sequence(
+            [Number(3, Red), Number(2, Green), Flower].iter()
+        ));
+    }
+
+    #[test]
+    fn move_errors() {
+        use self::Color::*;
+        use self::Suite::*;
+
+        let stack = |role, cards: &[Suite]| {
+            let mut stack = Stack::new(role);
+            for &card in cards {
+                stack.push_card(card);
+            }
+            stack
+        };
+
+        let sorting = stack(StackRole::Sorting, &[Number(9, Red), Number(5, Green)]);
+        assert_eq!(
+            check_move(&sorting, Number(4, Red), 2, Variant::Standard),
+            Ok(())
+        );
+        assert_eq!(
+            check_move(&sorting, Number(4, Green), 1, Variant::Standard),
+            Err(MoveError::WrongColor)
+        );
+        assert_eq!(
+            check_move(&sorting, Number(3, Red), 1, Variant::Standard),
+            Err(MoveError::NotOneLower)
+        );
+        assert_eq!(
+            check_move(&sorting, Dragon(Red), 1, Variant::Standard),
+            Err(MoveError::NotANumber)
+        );
+        assert_eq!(
+            check_move(&sorting, Dragon(Red), 1, Variant::DragonsAnywhere),
+            Ok(())
+        );
+        assert_eq!(check_drag(&sorting, 0), Err(MoveError::NotASequence));
+        assert_eq!(check_drag(&sorting, 2), Err(MoveError::NoCards));
+
+        let cell = stack(StackRole::Dragon, &[]);
+        assert_eq!(
+            check_move(&cell, Number(4, Red), 2, Variant::Standard),
+            Err(MoveError::TooManyCards)
+        );
+        let locked = stack(StackRole::Dragon, &[FaceDown; 4]);
+        assert_eq!(
+            check_move(&locked, Flower, 1, Variant::Standard),
+            Err(MoveError::DragonSlotLocked)
+        );
+        assert_eq!(check_drag(&locked, 3), Err(MoveError::DragonSlotLocked));
+        let column = stack(StackRole::Sorting, &[FaceDown; 4]);
+        assert_eq!(
+            check_move(&column, Number(9, Red), 1, Variant::Standard),
+            Err(MoveError::DragonSlotLocked)
+        );
+        assert_eq!(check_drag(&column, 3), Err(MoveError::DragonSlotLocked));
+
+        let flower = stack(StackRole::Flower, &[]);
+        assert_eq!(
+            check_move(&flower, Dragon(White), 1, Variant::Standard),
+            Err(MoveError::FlowerOnly)
+        );
+
+        let target = stack(StackRole::Target, &[Number(1, White)]);
+        assert_eq!(
+            check_move(&target, Number(2, White), 1, Variant::Standard),
+            Ok(())
+        );
+        assert_eq!(
+            check_move(&target, Number(3, White), 1, Variant::Standard),
+            Err(MoveError::NotOneHigher)
+        );
+        assert_eq!(check_drag(&target, 0), Err(MoveError::NotMovable));
+
+        let generic = stack(StackRole::Generic, &[Flower]);
+        assert_eq!(
+            check_move(&generic, Flower, 1, Variant::Standard),
+            Err(MoveError::InvalidStack)
+        );
+        assert_eq!(check_drag(&generic, 0), Err(MoveError::InvalidStack));
+
+        let mut stacks = Variant::Standard.empty_table();
+        for stack in &mut stacks[..4] {
+            stack.push_card(Dragon(Red));
+        }
+        assert_eq!(
+            check_button(Red, stacks.iter(), Variant::Standard),
+            Some((8, [0, 1, 2, 3]))
+        );
+        stacks[4].push_card(Dragon(Red));
+        assert_eq!(check_button(Red, stacks.iter(), Variant::Standard), None);
+    }
+}
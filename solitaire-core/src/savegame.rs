@@ -0,0 +1,166 @@
+use std::fmt;
+
+use notation::{self, ParseError};
+use rules::Move;
+use types::*;
+use variant::Variant;
+
+/// An interrupted game, to be resumed later.
+///
+/// The file is line based:
+///
+/// ```text
+/// seed 5a3c9e1f07b2d468
+/// variant no-flower
+/// elapsed 83.2
+/// stack sorting R5 G4 W3
+/// stack dragon X X X X
+/// undo 2>4x3 0>12x1
+/// redo DR>8:0,1,2,8
+/// ```
+///
+/// Stacks are listed in the order of `Variant::table`, and moves are written in full as described
+/// in `notation`, referring to stacks by that index.
+/// The `variant` line is left out for the standard rules.
+pub struct SaveGame {
+    pub seed: u64,
+    pub variant: Variant,
+    pub elapsed: f32,
+    pub stacks: Vec<Stack>,
+    pub history: Vec<Vec<Move>>,
+    pub undone: Vec<Vec<Move>>,
+}
+
+impl SaveGame {
+    /// Read a save game. Returns `None` if it is corrupt.
+    pub fn parse(s: &str) -> Option<SaveGame> {
+        let mut save = SaveGame {
+            seed: 0,
+            variant: Variant::Standard,
+            elapsed: 0.0,
+            stacks: Vec::new(),
+            history: Vec::new(),
+            undone: Vec::new(),
+        };
+        let mut seed = None;
+
+        for line in s.lines() {
+            let mut tokens = line.split_whitespace();
+            match tokens.next() {
+                Some("seed") => seed = tokens.next().and_then(|t| u64::from_str_radix(t, 16).ok()),
+                Some("variant") => save.variant = tokens.next()?.parse().ok()?,
+                Some("elapsed") => save.elapsed = tokens.next()?.parse().ok()?,
+                Some("stack") => save.stacks.push(parse_stack(line)?),
+                Some("undo") => save.history.push(parse_step(tokens)?),
+                Some("redo") => save.undone.push(parse_step(tokens)?),
+                Some(_) => return None,
+                None => continue,
+            }
+        }
+
+        save.seed = seed?;
+        Some(save)
+    }
+}
+
+impl fmt::Display for SaveGame {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "seed {:016x}", self.seed)?;
+        if self.variant != Variant::Standard {
+            writeln!(f, "variant {}", self.variant)?;
+        }
+        writeln!(f, "elapsed {}", self.elapsed)?;
+        for stack in &self.stacks {
+            writeln!(f, "stack {}", stack)?;
+        }
+        for &(key, steps) in &[("undo", &self.history), ("redo", &self.undone)] {
+            for step in steps.iter() {
+                write!(f, "{}", key)?;
+                for &m in step {
+                    write!(f, " {}", notation::write_full_move(m))?;
+                }
+                writeln!(f)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Read only the `stack` lines of a save game, e.g. to analyze a position with the solver.
+pub fn parse_board(s: &str) -> Option<Vec<Stack>> {
+    s.lines()
+        .filter(|line| line.split_whitespace().next() == Some("stack"))
+        .map(parse_stack)
+        .collect()
+}
+
+fn parse_stack(line: &str) -> Option<Stack> {
+    line.trim_start()
+        .trim_start_matches("stack")
+        .parse()
+        .map_err(|e: ParseError| warn!("Invalid stack in save game: {}", e))
+        .ok()
+}
+
+fn parse_step<'a, I: Iterator<Item = &'a str>>(tokens: I) -> Option<Vec<Move>> {
+    tokens.map(|t| notation::parse_full_move(t).ok()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn roundtrip() {
+        use self::Color::*;
+        use self::Suite::*;
+
+        let mut sorting = Stack::new(StackRole::Sorting);
+        sorting.push_card(Number(5, Red));
+        sorting.push_card(Dragon(Green));
+        sorting.push_card(Flower);
+        let mut dragon = Stack::new(StackRole::Dragon);
+        for _ in 0..4 {
+            dragon.push_card(FaceDown);
+        }
+
+        let save = SaveGame {
+            seed: 0x5a3c_9e1f_07b2_d468,
+            variant: Variant::TwoCells,
+            elapsed: 83.5,
+            stacks: vec![sorting, dragon, Stack::new(StackRole::Target)],
+            history: vec![
+                vec![Move::Cards(1, 0, 3), Move::Cards(2, 0, 1)],
+                vec![Move::Button(White, 1, [0, 1, 2, 1])],
+            ],
+            undone: vec![vec![Move::Cards(0, 2, 1)]],
+        };
+
+        let loaded = SaveGame::parse(&save.to_string()).unwrap();
+        assert_eq!(loaded.seed, save.seed);
+        assert_eq!(loaded.variant, save.variant);
+        assert_eq!(loaded.elapsed, save.elapsed);
+        assert_eq!(loaded.stacks, save.stacks);
+        assert_eq!(loaded.history, save.history);
+        assert_eq!(loaded.undone, save.undone);
+    }
+
+    #[test]
+    fn corrupt() {
+        assert!(SaveGame::parse("").is_none());
+        assert!(SaveGame::parse("seed 12\nstack sorting R0x").is_none());
+        assert!(SaveGame::parse("seed 12\nundo 1>2").is_none());
+        assert!(SaveGame::parse("seed 12\nfoo").is_none());
+        assert!(SaveGame::parse("seed 12\nvariant foo").is_none());
+    }
+
+    #[test]
+    fn board() {
+        let board =
+            parse_board("seed 12\nstack sorting R1 G2\nundo 1>2x1\nstack flower\n").unwrap();
+        assert_eq!(board.len(), 2);
+        assert_eq!(board[0].len(), 2);
+        assert_eq!(board[1].role, StackRole::Flower);
+        assert!(parse_board("stack sorting R1 G").is_none());
+    }
+}
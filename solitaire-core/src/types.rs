@@ -0,0 +1,80 @@
+use std::slice;
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum Color {
+    Red,
+    Green,
+    White,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum Suite {
+    FaceDown,
+    Flower,
+    Dragon(Color),
+    Number(u8, Color),
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum StackRole {
+    Dragon,
+    Flower,
+    Target,
+    Sorting,
+    Generic,
+    Animation,
+}
+
+#[derive(Clone, Hash, Eq, PartialEq, Debug)]
+pub struct Stack {
+    pub cards: Vec<Suite>,
+    pub role: StackRole,
+}
+
+impl Stack {
+    pub fn new(role: StackRole) -> Stack {
+        Stack {
+            cards: Vec::new(),
+            role,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.cards.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cards.is_empty()
+    }
+
+    pub fn iter(&self) -> slice::Iter<Suite> {
+        self.cards.iter()
+    }
+
+    pub fn top(&self) -> Option<Suite> {
+        self.cards.last().map(|s| *s)
+    }
+
+    pub fn push_card(&mut self, card: Suite) {
+        self.cards.push(card);
+    }
+
+    pub fn pop_card(&mut self) -> Option<Suite> {
+        self.cards.pop()
+    }
+
+    pub fn extend(&mut self, other: Stack) {
+        self.cards.extend(other.cards);
+    }
+
+    pub fn peek(&self, idx: usize) -> Suite {
+        self.cards[idx]
+    }
+
+    pub fn split(&mut self, at: usize) -> Stack {
+        Stack {
+            cards: self.cards.split_off(at),
+            role: StackRole::Generic,
+        }
+    }
+}
@@ -0,0 +1,109 @@
+use std::fmt;
+use std::str::FromStr;
+
+use types::*;
+
+/// A set of rules. Everything that differs between variants is asked from here, so a new
+/// variant only needs an entry in this enum and in the methods below.
+///
+/// A variant with a fourth suit would also need a fourth `Color`, with its own card faces and
+/// dragon button.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub enum Variant {
+    /// The game as it comes with SHENZHEN I/O.
+    #[default]
+    Standard,
+    /// There is no flower in the deck.
+    NoFlower,
+    /// Only two free cells. Collapsed dragons can also go to an empty sorting stack, which
+    /// then counts as cleared.
+    TwoCells,
+    /// A single dragon can be put on any sorting stack, not only on an empty one.
+    DragonsAnywhere,
+}
+
+impl Variant {
+    pub const ALL: [Variant; 4] = [
+        Variant::Standard,
+        Variant::NoFlower,
+        Variant::TwoCells,
+        Variant::DragonsAnywhere,
+    ];
+
+    pub fn free_cells(self) -> usize {
+        match self {
+            Variant::TwoCells => 2,
+            _ => 3,
+        }
+    }
+
+    pub fn has_flower(self) -> bool {
+        self != Variant::NoFlower
+    }
+
+    /// Whether collapsed dragons can go to an empty sorting stack.
+    pub fn dragons_to_sorting(self) -> bool {
+        self == Variant::TwoCells
+    }
+
+    /// Whether a dragon can be put on top of other cards on a sorting stack.
+    pub fn dragons_anywhere(self) -> bool {
+        self == Variant::DragonsAnywhere
+    }
+
+    /// Roles of the stacks on the table. Moves refer to stacks by their index in this list.
+    ///
+    /// The flower slot is part of every table, it just stays empty without a flower.
+    pub fn table(self) -> Vec<StackRole> {
+        let mut table = vec![StackRole::Sorting; 8];
+        table.extend(vec![StackRole::Dragon; self.free_cells()]);
+        table.push(StackRole::Flower);
+        table.extend(vec![StackRole::Target; 3]);
+        table
+    }
+
+    /// A table of empty stacks, in the order of `table`.
+    pub fn empty_table(self) -> Vec<Stack> {
+        self.table().into_iter().map(Stack::new).collect()
+    }
+
+    /// Every card of the variant, in a fixed order.
+    pub fn deck(self) -> Vec<Suite> {
+        let colors = [Color::Red, Color::Green, Color::White];
+        let mut cards = Vec::with_capacity(40);
+        for _ in 0..4 {
+            cards.extend(colors.iter().map(|&c| Suite::Dragon(c)));
+        }
+        for i in 1..10 {
+            cards.extend(colors.iter().map(|&c| Suite::Number(i, c)));
+        }
+        if self.has_flower() {
+            cards.push(Suite::Flower);
+        }
+        cards
+    }
+}
+
+impl fmt::Display for Variant {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match *self {
+            Variant::Standard => "standard",
+            Variant::NoFlower => "no-flower",
+            Variant::TwoCells => "two-cells",
+            Variant::DragonsAnywhere => "dragons-anywhere",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for Variant {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Variant, ()> {
+        Variant::ALL
+            .iter()
+            .cloned()
+            .find(|v| v.to_string() == s)
+            .ok_or(())
+    }
+}
@@ -41,16 +41,35 @@ impl GameState {
         }
 
         for e in finished.into_iter() {
-            let idx = self.ent_lookup[&e];
-            if let Some(target) = self.animations[idx].take().unwrap().target_stack {
-                let tidx = self.ent_lookup[&target];
-                let card = self.stacks[idx].take().unwrap().pop_card().unwrap();
-                self.stacks[tidx].as_mut().unwrap().push_card(card);
-                self.dirty = true;
-            }
-            self.remove_entity(e);
+            self.finish_animation(e);
         }
 
         busy
     }
+
+    /// Immediately complete all running animations.
+    pub fn finish_animation_system(&mut self) {
+        let running: Vec<_> = self
+            .animations
+            .iter()
+            .zip(self.entities.iter())
+            .filter_map(|x| x.all())
+            .map(|(_, &e)| e)
+            .collect();
+
+        for e in running {
+            self.finish_animation(e);
+        }
+    }
+
+    fn finish_animation(&mut self, e: Entity) {
+        let idx = self.ent_lookup[&e];
+        if let Some(target) = self.animations[idx].take().unwrap().target_stack {
+            let tidx = self.ent_lookup[&target];
+            let cards = self.stacks[idx].take().unwrap();
+            self.stacks[tidx].as_mut().unwrap().extend(cards);
+            self.dirty = true;
+        }
+        self.remove_entity(e);
+    }
 }
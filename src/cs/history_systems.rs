@@ -0,0 +1,82 @@
+use types::*;
+
+use super::GameState;
+
+impl GameState {
+    /// Start a new step in the move history. Redoing is no longer possible afterwards.
+    pub fn record_action(&mut self, action: Action) {
+        self.history.push(vec![action]);
+        self.undone.clear();
+        self.hint = None;
+    }
+
+    /// Automatic moves belong to the step that caused them, so they are undone together.
+    pub fn record_automove(&mut self, action: Action) {
+        if let Some(step) = self.history.last_mut() {
+            step.push(action);
+        }
+    }
+
+    pub fn history(&self) -> &[Vec<Action>] {
+        &self.history
+    }
+
+    pub fn undone(&self) -> &[Vec<Action>] {
+        &self.undone
+    }
+
+    pub fn set_history(&mut self, history: Vec<Vec<Action>>, undone: Vec<Vec<Action>>) {
+        self.history = history;
+        self.undone = undone;
+    }
+
+    pub fn undo_system(&mut self, speed: f32) {
+        if self.busy() || self.drag_lock.is_some() {
+            return;
+        }
+
+        let step = match self.history.pop() {
+            Some(step) => step,
+            None => return,
+        };
+        if self.play_actions(&step, true, speed).is_err() {
+            self.history.push(step);
+            return;
+        }
+        self.undone.push(step);
+        self.hint = None;
+    }
+
+    pub fn redo_system(&mut self, speed: f32) {
+        if self.busy() || self.drag_lock.is_some() {
+            return;
+        }
+
+        let step = match self.undone.pop() {
+            Some(step) => step,
+            None => return,
+        };
+        if self.play_actions(&step, false, speed).is_err() {
+            self.undone.push(step);
+            return;
+        }
+        self.history.push(step);
+        self.hint = None;
+    }
+
+    /// Position of the first free slot on a stack.
+    pub fn top_position(&self, e: Entity) -> Point2 {
+        let stack = self.get_stack(e).unwrap();
+        let pos = self.get_position(e).unwrap();
+        pos + stack.get_stackshift(&self.shifts) * stack.len() as f32
+    }
+
+    pub fn set_button_state(&mut self, color: Color, state: ButtonState) {
+        for b in self.buttons.iter_mut().filter_map(|b| b.as_mut()) {
+            if b.color == color {
+                b.state = state;
+                b.stacks = None;
+            }
+        }
+    }
+}
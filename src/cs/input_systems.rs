@@ -1,7 +1,7 @@
 use rules;
 use types::*;
 use utils::all::All;
-use utils::bbox::BoundingBox;
+use utils::bbox::{self, BoundingBox};
 
 use super::GameState;
 
@@ -13,49 +13,25 @@ impl GameState {
             return;
         }
 
-        let mut animation = Vec::new();
-        {
-            let compound_iterator = self
-                .positions
-                .iter()
-                .zip(self.buttons.iter_mut())
-                .filter_map(|x| x.all())
-                .filter(|&(_, ref b)| b.state == ButtonState::Active);
-            'outer: for (p, b) in compound_iterator {
-                let dist = click_pos - p;
-                if dist.norm_squared() <= BUTTON_RADIUS_SQUARED {
-                    b.state = ButtonState::Down;
-                    let (target_stack, source_stacks) = b.stacks.unwrap();
-                    let t = self.ent_lookup[&target_stack];
-                    let target_pos = self.positions[t].unwrap();
-                    let mut sound_start = Sounds::Sweep;
-                    for e in source_stacks.iter() {
-                        let s = self.ent_lookup[&e];
-                        let stack = self.stacks[s].as_mut().unwrap();
-                        let pos = &self.positions[s].unwrap();
-
-                        let start_pos = pos + stack.get_stackshift() * (stack.len() - 1) as f32;
-                        stack.pop_card();
-
-                        let ani = Animation {
-                            target_pos,
-                            target_stack: Some(target_stack),
-                            start_delay: 0.0,
-                            time_left: 0.3,
-                            sound_start,
-                            sound_stop: Sounds::None,
-                        };
-                        animation.push((start_pos, ani));
-                        sound_start = Sounds::None; // play only one sound for all cards
-                    }
-                    break 'outer;
-                }
+        let action = self
+            .positions
+            .iter()
+            .zip(self.buttons.iter())
+            .filter_map(|x| -> Option<(&Point2, &Button)> { x.all() })
+            .filter(|&(_, b)| b.state == ButtonState::Active)
+            .find(|&(p, _)| (click_pos - p).norm_squared() <= BUTTON_RADIUS_SQUARED)
+            .and_then(|(_, b)| {
+                b.stacks.map(|(target, sources)| Action::Dragons {
+                    color: b.color,
+                    target,
+                    sources,
+                })
+            });
+        if let Some(action) = action {
+            if self.play_actions(&[action], false, 1.0).is_ok() {
+                self.record_action(action);
             }
         }
-        for (start_pos, ani) in animation {
-            self.animate(Suite::FaceDown, start_pos, 100.0, ani);
-            self.dirty = true;
-        }
     }
 
     pub fn begin_drag_system(&mut self, mouse_pos: Point2, res: &mut Resources) {
@@ -67,48 +43,55 @@ impl GameState {
             return;
         }
 
-        let mut hit = None;
-        {
-            let compound_iterator = self
-                .positions
-                .iter()
-                .zip(self.stacks.iter_mut())
-                .zip(self.entities.iter())
-                .filter_map(|x| x.all());
+        let (e, i, card_pos) = match self.card_at(mouse_pos) {
+            Some(hit) => hit,
+            None => return,
+        };
+        res.pickup_sound.play().unwrap();
+        let substack = {
+            let s = self.get_stack_mut(e).unwrap();
+            if rules::check_drag(s, i).is_err() {
+                return;
+            }
+            s.split(i)
+        };
+
+        self.hint = None;
+        let drg = self
+            .new_entity()
+            .with_position(card_pos)
+            .with_stack(substack)
+            .with_zorder(200.0)
+            .build();
+        self.drag_lock = Some((e, drg));
+    }
 
-            'outer: for (p, s, e) in compound_iterator {
-                if mouse_pos.x < p.x || mouse_pos.y < p.y {
-                    continue;
-                }
+    /// The stack of the card under `pos`, the index of the card in it and where the card is.
+    pub fn card_at(&self, pos: Point2) -> Option<(Entity, usize, Point2)> {
+        let compound_iterator = self
+            .positions
+            .iter()
+            .zip(self.stacks.iter())
+            .zip(self.entities.iter())
+            .filter_map(|x| x.all());
+
+        for (p, s, &e) in compound_iterator {
+            if pos.x < p.x || pos.y < p.y {
+                continue;
+            }
 
-                for i in (0..s.len()).rev() {
-                    let card_pos = p + s.get_stackshift() * i as f32;
-                    if mouse_pos.x >= card_pos.x
-                        && mouse_pos.x <= card_pos.x + CARD_WIDTH
-                        && mouse_pos.y >= card_pos.y
-                        && mouse_pos.y <= card_pos.y + CARD_HEIGHT
-                    {
-                        if rules::is_valid_drag(s, i) {
-                            let substack = s.split(i);
-                            hit = Some((card_pos, substack, *e));
-                        }
-                        res.pickup_sound.play().unwrap();
-                        break 'outer; // there can be only one
-                    }
+            for i in (0..s.len()).rev() {
+                let card_pos = p + s.get_stackshift(&self.shifts) * i as f32;
+                if pos.x >= card_pos.x
+                    && pos.x <= card_pos.x + CARD_WIDTH
+                    && pos.y >= card_pos.y
+                    && pos.y <= card_pos.y + CARD_HEIGHT
+                {
+                    return Some((e, i, card_pos)); // there can be only one
                 }
             }
         }
-
-        self.drag_lock = hit.map(|(p, s, e)| {
-            (
-                e,
-                self.new_entity()
-                    .with_position(p)
-                    .with_stack(s)
-                    .with_zorder(200.0)
-                    .build(),
-            )
-        });
+        None
     }
 
     pub fn do_drag_system(&mut self, mouse_rel: Vector2) {
@@ -116,6 +99,10 @@ impl GameState {
             return;
         }
 
+        if self.cursor.is_some() {
+            return; // the cards were picked up with the keyboard
+        }
+
         if let Some((_, ent)) = self.drag_lock {
             *self.get_position_mut(ent).unwrap() += mouse_rel;
         }
@@ -126,42 +113,140 @@ impl GameState {
             return;
         }
 
+        if self.drag_lock.is_some() {
+            self.drop_run(res);
+        }
+    }
+
+    /// Stacks the dragged run `drg` touches, the one it covers the most first.
+    fn stacks_under(&self, drg: Entity) -> Vec<Entity> {
+        let pos = *self.get_position(drg).unwrap();
+        let bb_drag = BoundingBox::new(pos.x, pos.x + CARD_WIDTH, pos.y, pos.y + CARD_HEIGHT);
+
+        let stacks = self
+            .positions
+            .iter()
+            .zip(self.stacks.iter())
+            .zip(self.entities.iter())
+            .filter_map(|x| x.all())
+            .filter(|&(_, _, &e)| e != drg)
+            .map(|(p, s, &e)| {
+                let q = p
+                    + s.get_stackshift(&self.shifts) * (s.len() as f32 - 1.0).max(0.0)
+                    + Vector2::new(CARD_WIDTH, CARD_HEIGHT);
+                (e, BoundingBox::new(p.x, q.x, p.y, q.y))
+            });
+        bbox::by_overlap(&bb_drag, stacks)
+    }
+
+    /// Whether the dragged run can be dropped on `e`. The stack it came from never takes it.
+    fn takes_run(&self, e: Entity) -> bool {
+        match self.drag_lock {
+            Some((src, drg)) => {
+                let run = self.get_stack(drg).unwrap();
+                e != src && rules::check_drop(self.get_stack(e).unwrap(), run, self.variant).is_ok()
+            }
+            None => false,
+        }
+    }
+
+    /// The stack the dragged run is held over: the one under the keyboard cursor, or the one it
+    /// covers the most.
+    fn aimed_stack(&self) -> Option<Entity> {
+        let (_, drg) = self.drag_lock?;
+        match self.cursor {
+            Some(cursor) => Some(cursor.stack),
+            None => self.stacks_under(drg).into_iter().next(),
+        }
+    }
+
+    /// Where the dragged run goes if it is dropped now: the stack under the keyboard cursor, or
+    /// the one it covers the most of those that take it.
+    fn drop_target(&self) -> Option<Entity> {
+        let (_, drg) = self.drag_lock?;
+        match self.cursor {
+            Some(cursor) => Some(cursor.stack).filter(|&e| self.takes_run(e)),
+            None => self
+                .stacks_under(drg)
+                .into_iter()
+                .find(|&e| self.takes_run(e)),
+        }
+    }
+
+    /// Stacks on the table that take the dragged run, and the one it goes to if dropped now.
+    pub fn drop_targets(&self) -> (Vec<Entity>, Option<Entity>) {
+        if self.drag_lock.is_none() {
+            return (Vec::new(), None);
+        }
+        let targets = self
+            .table_stacks()
+            .into_iter()
+            .map(|(e, _)| e)
+            .filter(|&e| self.takes_run(e))
+            .collect();
+        (targets, self.drop_target())
+    }
+
+    /// Put the dragged run on its drop target. Without one it goes back where it came from,
+    /// right away if it is held over its own stack, e.g. after a click, and flying otherwise.
+    pub fn drop_run(&mut self, res: &mut Resources) {
+        let target = self.drop_target();
+        let aimed = self.aimed_stack();
         if let Some((src, drg)) = self.drag_lock.take() {
             res.place_sound.play().unwrap();
+            // why the run does not fit where the player aimed
+            let mut error = aimed.filter(|&e| e != src).and_then(|e| {
+                let run = self.get_stack(drg).unwrap();
+                rules::check_drop(self.get_stack(e).unwrap(), run, self.variant).err()
+            });
 
+            let pos = *self.get_position(drg).unwrap();
             let idx = self.ent_lookup[&drg];
-            let mut d_stack = self.stacks[idx].take();
-            let pos = self.positions[idx].take().unwrap();
-
-            let bb_drag = BoundingBox::new(pos.x, pos.x + CARD_WIDTH, pos.y, pos.y + CARD_HEIGHT);
-
-            {
-                let compound_iterator = self
-                    .positions
-                    .iter()
-                    .zip(self.stacks.iter_mut())
-                    .filter_map(|x| x.all());
-                for (p, s) in compound_iterator {
-                    let q = p
-                        + s.get_stackshift() * (s.len() as f32 - 1.0).max(0.0)
-                        + Vector2::new(CARD_WIDTH, CARD_HEIGHT);
-                    let bb_target = BoundingBox::new(p.x, q.x, p.y, q.y);
-
-                    if bb_target.intersects(&bb_drag) {
-                        if rules::is_valid_drop(s, d_stack.as_ref().unwrap()) {
-                            s.extend(d_stack.take().unwrap());
-                            self.dirty = true;
-                            break;
-                        }
+            let run = self.stacks[idx].take().unwrap();
+            let n = run.len();
+            self.remove_entity(drg);
+
+            // the move is made on the whole table, with the run back where it was picked up
+            self.get_stack_mut(src).unwrap().extend(run);
+
+            if let Some(dst) = target {
+                let action = Action::Cards { src, dst, n };
+                match self.apply_actions(&[action], false) {
+                    Ok(_) => {
+                        self.record_action(action);
+                        return;
                     }
+                    Err(e) => error = Some(e),
                 }
+            } else if aimed == Some(src) {
+                return; // put back, e.g. after a click
             }
 
-            if let Some(ds) = d_stack {
+            // nowhere to go, so the run flies back
+            let run = {
                 let stack = self.get_stack_mut(src).unwrap();
-                stack.extend(ds);
-            }
+                let at = stack.len() - n;
+                stack.split(at)
+            };
+            let ani = Animation {
+                target_pos: self.top_position(src),
+                target_stack: Some(src),
+                start_delay: 0.0,
+                time_left: 0.2,
+                sound_start: Sounds::None,
+                sound_stop: Sounds::None,
+            };
+            self.animate_stack(run, pos, 200.0, ani);
+            self.drop_error = error;
+        }
+    }
 
+    /// Put a dragged run back where it came from.
+    pub fn cancel_drag_system(&mut self) {
+        if let Some((src, drg)) = self.drag_lock.take() {
+            let idx = self.ent_lookup[&drg];
+            let d_stack = self.stacks[idx].take().unwrap();
+            self.get_stack_mut(src).unwrap().extend(d_stack);
             self.remove_entity(drg);
         }
     }
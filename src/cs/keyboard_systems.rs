@@ -0,0 +1,262 @@
+use ggez::event::KeyCode;
+
+use rules;
+use types::*;
+use utils::all::All;
+
+use super::GameState;
+
+use resources::Resources;
+
+/// Offset of a run picked up with the keyboard from where it would be dropped.
+const HOLD_OFFSET: f32 = 12.0;
+
+/// The stack the keyboard works on, and how many of its top cards are selected.
+#[derive(Copy, Clone, Debug)]
+pub struct Cursor {
+    pub stack: Entity,
+    pub n: usize,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum Direction {
+    Left,
+    Right,
+    Up,
+    Down,
+}
+
+impl GameState {
+    /// Handle a key meant for the table. Returns whether the key was used.
+    ///
+    /// The arrow keys move the cursor between stacks, Up and Down also select more or fewer cards
+    /// of a sorting stack. The number keys jump to a sorting stack, Space picks up the selected
+    /// cards and drops them on the stack under the cursor. R, G and W press the dragon buttons,
+    /// D presses whichever one is active.
+    pub fn keyboard_system(&mut self, key: KeyCode, res: &mut Resources) -> bool {
+        if self.busy() {
+            return false;
+        }
+        if self.drag_lock.is_some() && self.cursor.is_none() {
+            return false; // the mouse is dragging cards
+        }
+
+        match key {
+            KeyCode::Left => self.move_cursor(Direction::Left),
+            KeyCode::Right => self.move_cursor(Direction::Right),
+            KeyCode::Up => self.move_cursor(Direction::Up),
+            KeyCode::Down => self.move_cursor(Direction::Down),
+            KeyCode::Key1 => self.jump_to_column(0),
+            KeyCode::Key2 => self.jump_to_column(1),
+            KeyCode::Key3 => self.jump_to_column(2),
+            KeyCode::Key4 => self.jump_to_column(3),
+            KeyCode::Key5 => self.jump_to_column(4),
+            KeyCode::Key6 => self.jump_to_column(5),
+            KeyCode::Key7 => self.jump_to_column(6),
+            KeyCode::Key8 => self.jump_to_column(7),
+            KeyCode::Space | KeyCode::Return => {
+                if self.drag_lock.is_some() {
+                    self.keyboard_drop(res);
+                } else {
+                    self.keyboard_pickup(res);
+                }
+            }
+            KeyCode::Escape if self.drag_lock.is_some() => self.cancel_drag_system(),
+            KeyCode::R => self.press_button(Some(Color::Red)),
+            KeyCode::G => self.press_button(Some(Color::Green)),
+            KeyCode::W => self.press_button(Some(Color::White)),
+            KeyCode::D => self.press_button(None),
+            _ => return false,
+        }
+        true
+    }
+
+    /// Hide the cursor, e.g. when the mouse takes over. Cards picked up with the keyboard go back.
+    pub fn hide_cursor(&mut self) {
+        if self.cursor.take().is_some() {
+            self.cancel_drag_system();
+        }
+    }
+
+    /// The cursor with its selection limited to the cards that are there.
+    pub fn cursor(&self) -> Option<Cursor> {
+        let cursor = self.cursor?;
+        let len = self.get_stack(cursor.stack)?.len();
+        Some(Cursor {
+            n: cursor.n.min(len),
+            ..cursor
+        })
+    }
+
+    /// Stacks of the table along with their positions, without dragged or animated cards.
+    pub fn table_stacks(&self) -> Vec<(Entity, Point2)> {
+        self.positions
+            .iter()
+            .zip(self.stacks.iter())
+            .zip(self.entities.iter())
+            .filter_map(|x| x.all())
+            .filter(|&(_, s, _)| s.role != StackRole::Generic && s.role != StackRole::Animation)
+            .map(|(&p, _, &e)| (e, p))
+            .collect()
+    }
+
+    fn move_cursor(&mut self, direction: Direction) {
+        let cursor = match self.cursor() {
+            Some(cursor) => cursor,
+            None => {
+                self.jump_to_column(0);
+                return;
+            }
+        };
+        let holding = self.drag_lock.is_some();
+
+        // select more or fewer cards before leaving the stack
+        let len = self.get_stack(cursor.stack).unwrap().len();
+        let n = match direction {
+            Direction::Up if !holding && cursor.n < len => {
+                let stack = self.get_stack(cursor.stack).unwrap();
+                if rules::check_drag(stack, len - cursor.n - 1).is_ok() {
+                    cursor.n + 1
+                } else {
+                    cursor.n
+                }
+            }
+            Direction::Down if !holding && cursor.n > 1 => cursor.n - 1,
+            _ => cursor.n,
+        };
+        if n != cursor.n {
+            self.set_cursor(cursor.stack, n);
+            return;
+        }
+
+        let here = *self.get_position(cursor.stack).unwrap();
+        let stacks = self.table_stacks();
+        let next = match direction {
+            Direction::Left | Direction::Right => {
+                let sign = if direction == Direction::Left {
+                    -1.0
+                } else {
+                    1.0
+                };
+                stacks
+                    .iter()
+                    .filter(|&&(_, p)| (p.y - here.y).abs() < 1.0 && (p.x - here.x) * sign > 0.0)
+                    .min_by(|a, b| {
+                        distance(here, a.1)
+                            .partial_cmp(&distance(here, b.1))
+                            .unwrap()
+                    })
+            }
+            Direction::Up | Direction::Down => {
+                let sign = if direction == Direction::Up {
+                    -1.0
+                } else {
+                    1.0
+                };
+                let row = stacks
+                    .iter()
+                    .map(|&(_, p)| p.y)
+                    .filter(|&y| (y - here.y) * sign >= 1.0)
+                    .min_by(|a, b| (a - here.y).abs().partial_cmp(&(b - here.y).abs()).unwrap());
+                row.and_then(|y| {
+                    stacks
+                        .iter()
+                        .filter(|&&(_, p)| (p.y - y).abs() < 1.0)
+                        .min_by(|a, b| {
+                            (a.1.x - here.x)
+                                .abs()
+                                .partial_cmp(&(b.1.x - here.x).abs())
+                                .unwrap()
+                        })
+                })
+            }
+        };
+        if let Some(&(e, _)) = next {
+            self.set_cursor(e, 1);
+        }
+    }
+
+    /// Put the cursor on the `i`th sorting stack from the left.
+    fn jump_to_column(&mut self, i: usize) {
+        let mut columns = self
+            .table_stacks()
+            .into_iter()
+            .filter(|&(e, _)| self.get_stack(e).unwrap().role == StackRole::Sorting)
+            .collect::<Vec<_>>();
+        columns.sort_by(|a, b| a.1.x.partial_cmp(&b.1.x).unwrap());
+        if let Some(&(e, _)) = columns.get(i) {
+            self.set_cursor(e, 1);
+        }
+    }
+
+    fn set_cursor(&mut self, stack: Entity, n: usize) {
+        self.cursor = Some(Cursor { stack, n });
+        if let Some((_, drg)) = self.drag_lock {
+            let pos = self.hold_position(stack);
+            *self.get_position_mut(drg).unwrap() = pos;
+        }
+    }
+
+    /// Where a run picked up with the keyboard hovers over `stack`.
+    fn hold_position(&self, stack: Entity) -> Point2 {
+        let s = self.get_stack(stack).unwrap();
+        let p = self.get_position(stack).unwrap();
+        p + s.get_stackshift(&self.shifts) * s.len() as f32 + Vector2::new(HOLD_OFFSET, HOLD_OFFSET)
+    }
+
+    fn keyboard_pickup(&mut self, res: &mut Resources) {
+        let cursor = match self.cursor() {
+            Some(cursor) => cursor,
+            None => {
+                self.jump_to_column(0);
+                return;
+            }
+        };
+        let src = cursor.stack;
+        let substack = {
+            let stack = self.get_stack_mut(src).unwrap();
+            let at = stack.len() - cursor.n;
+            if cursor.n == 0 || rules::check_drag(stack, at).is_err() {
+                return;
+            }
+            stack.split(at)
+        };
+        res.pickup_sound.play().unwrap();
+
+        self.hint = None;
+        let pos = self.hold_position(src);
+        let drg = self
+            .new_entity()
+            .with_position(pos)
+            .with_stack(substack)
+            .with_zorder(200.0)
+            .build();
+        self.drag_lock = Some((src, drg));
+        self.cursor = Some(Cursor { stack: src, n: 1 });
+    }
+
+    fn keyboard_drop(&mut self, res: &mut Resources) {
+        if let Some(cursor) = self.cursor {
+            self.drop_run(res);
+            self.set_cursor(cursor.stack, 1);
+        }
+    }
+
+    /// Press the button of a color, or the first active one.
+    fn press_button(&mut self, color: Option<Color>) {
+        let button = self
+            .positions
+            .iter()
+            .zip(self.buttons.iter())
+            .filter_map(|x| -> Option<(&Point2, &Button)> { x.all() })
+            .find(|&(_, b)| b.state == ButtonState::Active && color.unwrap_or(b.color) == b.color)
+            .map(|(&p, _)| p);
+        if let Some(p) = button {
+            self.button_click_system(p);
+        }
+    }
+}
+
+fn distance(a: Point2, b: Point2) -> f32 {
+    (b - a).norm()
+}
@@ -1,16 +1,22 @@
 use std::collections::HashMap;
 use std::slice;
 
+use ggez::event::KeyCode;
 use ggez::{Context, GameResult};
 
 mod animation_systems;
+mod history_systems;
 mod input_systems;
+mod keyboard_systems;
 mod render_systems;
 mod rule_systems;
 
 use resources::Resources;
+use rules::{AutomovePolicy, MoveError};
 use types::*;
+use variant::Variant;
 
+use self::keyboard_systems::Cursor;
 use self::render_systems::*;
 
 type Component<T> = Vec<Option<T>>;
@@ -33,6 +39,16 @@ pub struct GameState {
     render_queue: RenderQueue,
 
     drag_lock: Option<(Entity, Entity)>,
+    cursor: Option<Cursor>,
+
+    history: Vec<Vec<Action>>,
+    undone: Vec<Vec<Action>>,
+
+    hint: Option<Action>,
+    automove: AutomovePolicy,
+    variant: Variant,
+    shifts: StackShifts,
+    drop_error: Option<MoveError>,
 }
 
 impl GameState {
@@ -72,6 +88,13 @@ impl GameState {
         self.dirty = false;
 
         self.drag_lock = None;
+        self.cursor = None;
+
+        self.history.clear();
+        self.undone.clear();
+
+        self.hint = None;
+        self.drop_error = None;
     }
 
     pub fn iter(&self) -> slice::Iter<Entity> {
@@ -102,6 +125,50 @@ impl GameState {
         self.busy
     }
 
+    pub fn mark_dirty(&mut self) {
+        self.dirty = true;
+    }
+
+    /// Choose which cards move to the target stacks on their own. A replay turns this off,
+    /// since the recorded game already contains these moves.
+    pub fn set_automove_policy(&mut self, policy: AutomovePolicy) {
+        self.automove = policy;
+    }
+
+    pub fn automove_policy(&self) -> AutomovePolicy {
+        self.automove
+    }
+
+    /// Choose the rules that moves are checked against.
+    pub fn set_variant(&mut self, variant: Variant) {
+        self.variant = variant;
+    }
+
+    /// Choose how far apart the cards of each kind of stack are drawn.
+    pub fn set_shifts(&mut self, shifts: StackShifts) {
+        self.shifts = shifts;
+    }
+
+    pub fn shifts(&self) -> &StackShifts {
+        &self.shifts
+    }
+
+    /// Highlight a suggested move until the table changes.
+    pub fn show_hint(&mut self, action: Action) {
+        self.hint = Some(action);
+    }
+
+    /// Why the last dropped cards were put back, if they were.
+    pub fn take_drop_error(&mut self) -> Option<MoveError> {
+        self.drop_error.take()
+    }
+
+    /// Bring the table into a resting state where all cards are on their stacks.
+    pub fn settle(&mut self) {
+        self.cancel_drag_system();
+        self.finish_animation_system();
+    }
+
     pub fn run_update(&mut self, dt: f32, res: &mut Resources) -> bool {
         self.busy = false;
         self.busy |= self.animation_update_system(dt, res);
@@ -110,22 +177,64 @@ impl GameState {
         self.busy
     }
 
-    pub fn run_render(&mut self, ctx: &mut Context, res: &mut Resources) -> GameResult<()> {
-        self.render_queue.background_render_system(ctx, res)?;
+    pub fn run_render(
+        &mut self,
+        ctx: &mut Context,
+        res: &mut Resources,
+        seed: u64,
+    ) -> GameResult<()> {
+        self.render_queue.background_render_system(ctx, res, seed)?;
         self.render_queue
             .button_render_system(ctx, res, &self.positions, &self.buttons)?;
-        self.render_queue
-            .stack_render_system(&self.positions, &self.stacks, &self.zorder)?;
+        self.render_queue.stack_render_system(
+            &self.positions,
+            &self.stacks,
+            &self.zorder,
+            &self.shifts,
+        )?;
+        self.render_queue.hint_render_system(
+            self.hint,
+            &self.ent_lookup,
+            &self.positions,
+            &self.stacks,
+            &self.buttons,
+            &self.shifts,
+        )?;
+        let (targets, hovered) = self.drop_targets();
+        self.render_queue.drop_target_render_system(
+            &targets,
+            hovered,
+            &self.ent_lookup,
+            &self.positions,
+            &self.stacks,
+            &self.shifts,
+        )?;
+        let held = self.drag_lock.is_some();
+        self.render_queue.cursor_render_system(
+            self.cursor(),
+            held,
+            &self.ent_lookup,
+            &self.positions,
+            &self.stacks,
+            &self.shifts,
+        )?;
         self.render_queue.render(ctx, res)?;
         Ok(())
     }
 
     pub fn handle_mouse_button_down(&mut self, x: f32, y: f32, res: &mut Resources) {
+        self.hide_cursor();
         let pos = Point2::from([x, y]);
         self.begin_drag_system(pos, res);
         self.button_click_system(pos);
     }
 
+    /// Send the clicked card to where it fits best, without dragging it there.
+    pub fn handle_auto_send(&mut self, x: f32, y: f32) {
+        self.hide_cursor();
+        self.auto_send_system(Point2::new(x, y));
+    }
+
     pub fn handle_mouse_button_up(&mut self, _x: f32, _y: f32, res: &mut Resources) {
         self.done_drag_system(res);
     }
@@ -135,10 +244,28 @@ impl GameState {
         self.do_drag_system(dpos);
     }
 
+    pub fn handle_key(&mut self, key: KeyCode, res: &mut Resources) -> bool {
+        self.keyboard_system(key, res)
+    }
+
+    /// Take back the last step, animated `speed` times as fast as usual.
+    pub fn handle_undo(&mut self, speed: f32) {
+        self.undo_system(speed);
+    }
+
+    /// Make the last step that was taken back again, animated `speed` times as fast as usual.
+    pub fn handle_redo(&mut self, speed: f32) {
+        self.redo_system(speed);
+    }
+
     pub fn animate(&mut self, card: Suite, pos: Point2, z: f32, ani: Animation) {
         let mut stack = Stack::new(StackRole::Animation);
         stack.push_card(card);
+        self.animate_stack(stack, pos, z, ani);
+    }
 
+    /// Animate a whole stack of cards at once. They land on the target stack in their current order.
+    pub fn animate_stack(&mut self, stack: Stack, pos: Point2, z: f32, ani: Animation) {
         self.new_entity()
             .with_position(pos)
             .with_zorder(z)
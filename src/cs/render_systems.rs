@@ -1,19 +1,31 @@
 use std::cmp;
-use std::collections::BinaryHeap;
+use std::collections::{BinaryHeap, HashMap};
 use std::f32;
 
 use ggez::graphics;
-use ggez::graphics::{DrawParam, Drawable};
+use ggez::graphics::{DrawMode, DrawParam, Drawable, Mesh, Rect};
 use ggez::{Context, GameResult};
 
 use resources::Resources;
 use types::*;
 use utils::all::All;
 
+use super::keyboard_systems::Cursor;
 use super::Component;
 
+const HINT_Z: f32 = 300.0;
+const CURSOR_Z: f32 = 310.0;
+/// Above the cards on the table, but below dragged and moving ones.
+const DROP_Z: f32 = 50.0;
+
 enum DrawCommand {
     Card { z: f32, pos: Point2, suite: Suite },
+    Glow { z: f32, rect: Rect },
+    Outline { z: f32, rect: Rect },
+    Cursor { z: f32, rect: Rect },
+    Ring { z: f32, pos: Point2, radius: f32 },
+    DropTarget { z: f32, rect: Rect },
+    HoveredTarget { z: f32, rect: Rect },
 }
 
 #[derive(Default)]
@@ -26,6 +38,45 @@ impl RenderQueue {
         while let Some(cmd) = self.queue.pop() {
             match cmd {
                 DrawCommand::Card { pos, suite, .. } => self.render_card(pos, suite, ctx, res)?,
+                DrawCommand::Glow { rect, .. } => {
+                    let glow = graphics::Color::new(1.0, 0.9, 0.3, 0.3);
+                    let mesh = Mesh::new_rectangle(ctx, DrawMode::fill(), rect, glow)?;
+                    graphics::draw(ctx, &mesh, DrawParam::new())?;
+                    let mesh = Mesh::new_rectangle(ctx, DrawMode::stroke(4.0), rect, hint_color())?;
+                    graphics::draw(ctx, &mesh, DrawParam::new())?;
+                }
+                DrawCommand::Outline { rect, .. } => {
+                    let mesh = Mesh::new_rectangle(ctx, DrawMode::stroke(4.0), rect, hint_color())?;
+                    graphics::draw(ctx, &mesh, DrawParam::new())?;
+                }
+                DrawCommand::Cursor { rect, .. } => {
+                    let mesh =
+                        Mesh::new_rectangle(ctx, DrawMode::stroke(3.0), rect, cursor_color())?;
+                    graphics::draw(ctx, &mesh, DrawParam::new())?;
+                }
+                DrawCommand::DropTarget { rect, .. } => {
+                    let mesh =
+                        Mesh::new_rectangle(ctx, DrawMode::stroke(2.0), rect, drop_color(0.5))?;
+                    graphics::draw(ctx, &mesh, DrawParam::new())?;
+                }
+                DrawCommand::HoveredTarget { rect, .. } => {
+                    let fill = Mesh::new_rectangle(ctx, DrawMode::fill(), rect, drop_color(0.2))?;
+                    graphics::draw(ctx, &fill, DrawParam::new())?;
+                    let mesh =
+                        Mesh::new_rectangle(ctx, DrawMode::stroke(5.0), rect, drop_color(1.0))?;
+                    graphics::draw(ctx, &mesh, DrawParam::new())?;
+                }
+                DrawCommand::Ring { pos, radius, .. } => {
+                    let mesh = Mesh::new_circle(
+                        ctx,
+                        DrawMode::stroke(4.0),
+                        pos,
+                        radius,
+                        0.5,
+                        hint_color(),
+                    )?;
+                    graphics::draw(ctx, &mesh, DrawParam::new())?;
+                }
             }
         }
         Ok(())
@@ -35,15 +86,19 @@ impl RenderQueue {
         &self,
         ctx: &mut Context,
         res: &mut Resources,
+        seed: u64,
     ) -> GameResult<()> {
         //graphics::set_color(ctx, graphics::Color::new(1.0, 1.0, 1.0, 1.0))?;
         //graphics::draw(ctx, &res.table_image, Point2::new(0.0, 0.0), 0.0)?;
+        // the bars beside the table when the window has another aspect ratio
+        graphics::clear(ctx, graphics::Color::new(0.0, 0.0, 0.0, 1.0));
         graphics::draw(ctx, &res.table_image, DrawParam::new())?;
 
         //graphics::set_color(ctx, graphics::Color::new(0.0, 0.0, 0.0, 1.0))?;
+        let table = res.layout.size;
         let txt = format!("Win Count: {}", res.wins());
         let text = res.get_text(ctx, &txt)?;
-        let pos = Point2::new(0.0, 806.0 - text.height(ctx) as f32);
+        let pos = Point2::new(0.0, table.y - text.height(ctx) as f32);
         //graphics::draw(ctx, text,pos, 0.0)?;
         graphics::draw(
             ctx,
@@ -53,6 +108,20 @@ impl RenderQueue {
                 .color(graphics::Color::new(0.0, 0.0, 0.0, 1.0)),
         )?;
 
+        let txt = format!("Deal {:016x}", seed);
+        let text = res.get_text(ctx, &txt)?;
+        let pos = Point2::new(
+            table.x - text.width(ctx) as f32,
+            table.y - text.height(ctx) as f32,
+        );
+        graphics::draw(
+            ctx,
+            text,
+            DrawParam::new()
+                .dest(pos)
+                .color(graphics::Color::new(0.0, 0.0, 0.0, 1.0)),
+        )?;
+
         Ok(())
     }
 
@@ -83,6 +152,7 @@ impl RenderQueue {
         pos: &Component<Point2>,
         stk: &Component<Stack>,
         zs: &Component<f32>,
+        shifts: &StackShifts,
     ) -> GameResult<()> {
         let compound_iterator = pos
             .iter()
@@ -91,7 +161,7 @@ impl RenderQueue {
             .filter_map(|x| -> Option<(_, _, &f32)> { x.all() });
         for (p, s, &z) in compound_iterator {
             let mut pos = *p;
-            let dpos = s.get_stackshift();
+            let dpos = s.get_stackshift(shifts);
 
             for (i, card) in s.iter().enumerate() {
                 let z = z + 0.1 * i as f32;
@@ -107,6 +177,138 @@ impl RenderQueue {
         Ok(())
     }
 
+    /// Highlight the cards to move next and where they go.
+    pub fn hint_render_system(
+        &mut self,
+        hint: Option<Action>,
+        lookup: &HashMap<Entity, usize>,
+        pos: &Component<Point2>,
+        stk: &Component<Stack>,
+        btn: &Component<Button>,
+        shifts: &StackShifts,
+    ) -> GameResult<()> {
+        let stack_at = |e: Entity| -> Option<(Point2, &Stack)> {
+            let idx = lookup[&e];
+            Some((pos[idx]?, stk[idx].as_ref()?))
+        };
+        let card_rect = |p: Point2| Rect::new(p.x, p.y, CARD_WIDTH, CARD_HEIGHT);
+
+        match hint {
+            None => {}
+            Some(Action::Cards { src, dst, n }) => {
+                if let Some((p, s)) = stack_at(src) {
+                    let start = p + s.get_stackshift(shifts) * (s.len() - n) as f32;
+                    let end = p + s.get_stackshift(shifts) * (s.len() - 1) as f32;
+                    let mut rect = card_rect(start);
+                    rect.h += end.y - start.y;
+                    self.queue.push(DrawCommand::Glow { z: HINT_Z, rect });
+                }
+                if let Some((p, s)) = stack_at(dst) {
+                    let top = p + s.get_stackshift(shifts) * s.len() as f32;
+                    self.queue.push(DrawCommand::Outline {
+                        z: HINT_Z,
+                        rect: card_rect(top),
+                    });
+                }
+            }
+            Some(Action::Dragons {
+                color,
+                target,
+                sources,
+            }) => {
+                for &e in &sources {
+                    if let Some((p, s)) = stack_at(e) {
+                        let top = p + s.get_stackshift(shifts) * (s.len() - 1) as f32;
+                        self.queue.push(DrawCommand::Glow {
+                            z: HINT_Z,
+                            rect: card_rect(top),
+                        });
+                    }
+                }
+                if let Some((p, _)) = stack_at(target) {
+                    self.queue.push(DrawCommand::Outline {
+                        z: HINT_Z,
+                        rect: card_rect(p),
+                    });
+                }
+                let button = pos
+                    .iter()
+                    .zip(btn.iter())
+                    .filter_map(|x| -> Option<(_, &Button)> { x.all() })
+                    .find(|&(_, b)| b.color == color);
+                if let Some((&p, _)) = button {
+                    self.queue.push(DrawCommand::Ring {
+                        z: HINT_Z,
+                        pos: p,
+                        radius: BUTTON_RADIUS + 4.0,
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Mark where dragged cards can be dropped, and more clearly where they go if dropped now.
+    pub fn drop_target_render_system(
+        &mut self,
+        targets: &[Entity],
+        hovered: Option<Entity>,
+        lookup: &HashMap<Entity, usize>,
+        pos: &Component<Point2>,
+        stk: &Component<Stack>,
+        shifts: &StackShifts,
+    ) -> GameResult<()> {
+        for &e in targets {
+            let idx = lookup[&e];
+            let (p, s) = match (pos[idx], stk[idx].as_ref()) {
+                (Some(p), Some(s)) => (p, s),
+                _ => continue,
+            };
+            let top = p + s.get_stackshift(shifts) * s.len() as f32;
+            let rect = Rect::new(top.x, top.y, CARD_WIDTH, CARD_HEIGHT);
+            if Some(e) == hovered {
+                self.queue
+                    .push(DrawCommand::HoveredTarget { z: DROP_Z, rect });
+            } else {
+                self.queue.push(DrawCommand::DropTarget { z: DROP_Z, rect });
+            }
+        }
+        Ok(())
+    }
+
+    /// Frame the cards selected with the keyboard, or where held cards would be dropped.
+    pub fn cursor_render_system(
+        &mut self,
+        cursor: Option<Cursor>,
+        held: bool,
+        lookup: &HashMap<Entity, usize>,
+        pos: &Component<Point2>,
+        stk: &Component<Stack>,
+        shifts: &StackShifts,
+    ) -> GameResult<()> {
+        let cursor = match cursor {
+            Some(cursor) => cursor,
+            None => return Ok(()),
+        };
+        let idx = lookup[&cursor.stack];
+        let (p, s) = match (pos[idx], stk[idx].as_ref()) {
+            (Some(p), Some(s)) => (p, s),
+            _ => return Ok(()),
+        };
+
+        let shift = s.get_stackshift(shifts);
+        let (first, last) = if held || cursor.n == 0 {
+            (s.len(), s.len())
+        } else {
+            (s.len() - cursor.n, s.len() - 1)
+        };
+        let start = p + shift * first as f32;
+        let end = p + shift * last as f32;
+        let rect = Rect::new(start.x, start.y, CARD_WIDTH, CARD_HEIGHT + end.y - start.y);
+        self.queue.push(DrawCommand::Cursor { z: CURSOR_Z, rect });
+        Ok(())
+    }
+
     fn render_card(
         &self,
         pos: Point2,
@@ -242,11 +444,29 @@ impl RenderQueue {
 impl DrawCommand {
     fn get_z(&self) -> f32 {
         match *self {
-            DrawCommand::Card { z, .. } => z,
+            DrawCommand::Card { z, .. }
+            | DrawCommand::Glow { z, .. }
+            | DrawCommand::Outline { z, .. }
+            | DrawCommand::Cursor { z, .. }
+            | DrawCommand::Ring { z, .. }
+            | DrawCommand::DropTarget { z, .. }
+            | DrawCommand::HoveredTarget { z, .. } => z,
         }
     }
 }
 
+fn hint_color() -> graphics::Color {
+    graphics::Color::new(1.0, 0.8, 0.1, 1.0)
+}
+
+fn cursor_color() -> graphics::Color {
+    graphics::Color::new(0.2, 0.6, 1.0, 1.0)
+}
+
+fn drop_color(alpha: f32) -> graphics::Color {
+    graphics::Color::new(0.4, 1.0, 0.5, alpha)
+}
+
 impl cmp::Eq for DrawCommand {}
 
 impl cmp::Ord for DrawCommand {
@@ -1,39 +1,45 @@
-use rules;
+use std::collections::HashMap;
+
+use board::Board;
+use rules::{Move, MoveError};
 use types::*;
 use utils::all::All;
 
 use super::GameState;
 
+/// Cards of a move on their way from where they were to the stack they are on now.
+pub struct Flight {
+    stack: Entity,
+    n: usize,
+    from: Point2,
+    to: Point2,
+    /// index of the move among the ones made together
+    step: usize,
+}
+
 impl GameState {
     pub fn button_update_system(&mut self) {
         if !self.dirty || self.busy() {
             return;
         }
 
+        let (board, idx) = self.table();
+        let ents: Vec<Entity> = idx.iter().map(|&i| self.entities[i]).collect();
+
         for b in self.buttons.iter_mut().filter_map(|x| x.all()) {
             if b.state == ButtonState::Down {
                 continue;
             }
 
-            let (stacks, ents): (Vec<_>, Vec<Entity>) = self
-                .stacks
-                .iter()
-                .zip(self.entities.iter())
-                .filter_map(|(stack, e)| stack.as_ref().map(|s| (s, e)))
-                .unzip();
-
-            let r = rules::check_button(b.color, stacks.into_iter());
-
-            match r {
-                None => {
-                    b.state = ButtonState::Up;
-                    b.stacks = None;
-                    continue;
-                }
-                Some((t, s)) => {
+            match board.collapse_dragons(b.color) {
+                Some(Move::Button(_, t, s)) => {
                     b.state = ButtonState::Active;
                     b.stacks = Some((ents[t], [ents[s[0]], ents[s[1]], ents[s[2]], ents[s[3]]]))
                 }
+                _ => {
+                    b.state = ButtonState::Up;
+                    b.stacks = None;
+                }
             }
         }
     }
@@ -44,47 +50,224 @@ impl GameState {
         }
         self.dirty = false;
 
-        let auto_move;
-        {
-            let (stacks, idx): (Vec<_>, Vec<_>) = self
-                .stacks
-                .iter()
-                .enumerate()
-                .filter_map(|(i, stack)| stack.as_ref().map(|s| (s, i)))
-                .unzip();
+        let (board, idx) = self.table();
+        let action = match board.automove() {
+            Some(Move::Cards(t, s, n)) => Action::Cards {
+                src: self.entities[idx[s]],
+                dst: self.entities[idx[t]],
+                n,
+            },
+            _ => return,
+        };
+        if self.play_actions(&[action], false, 1.0).is_ok() {
+            self.record_automove(action);
+        }
+    }
 
-            auto_move = rules::get_automove(stacks.into_iter()).map(|(t, s)| (idx[t], idx[s]));
+    /// Send the card under `pos` and the ones on top of it to where they fit best: a target
+    /// stack, then a free dragon cell, then a sorting stack with cards, then an empty one.
+    pub fn auto_send_system(&mut self, pos: Point2) {
+        if self.busy() || self.drag_lock.is_some() {
+            return;
         }
 
-        if let Some((dst, src)) = auto_move {
-            let card;
-            let start_pos;
-            let target_pos;
-            let target_stack;
-            {
-                let s_stack = self.stacks[src].as_mut().unwrap();
-                card = s_stack.pop_card().unwrap();
-            }
-            {
-                let s_stack = self.stacks[src].as_ref().unwrap();
-                let s_pos = self.positions[src].as_ref().unwrap();
-                start_pos = s_pos + s_stack.get_stackshift() * s_stack.len() as f32;
-
-                target_stack = Some(self.entities[dst]);
-                let t_stack = self.stacks[dst].as_ref().unwrap();
-                let t_pos = self.positions[dst].as_ref().unwrap();
-                target_pos = t_pos + t_stack.get_stackshift() * t_stack.len() as f32;
+        let (src, i) = match self.card_at(pos) {
+            Some((e, i, _)) => (e, i),
+            None => return,
+        };
+        let (board, idx) = self.table();
+        let s = match idx.iter().position(|&j| self.entities[j] == src) {
+            Some(s) => s,
+            None => return,
+        };
+        let source = &board.stacks()[s];
+        let n = source.len() - i;
+
+        let rank = |stack: &Stack| match stack.role {
+            StackRole::Target | StackRole::Flower => 0,
+            StackRole::Dragon => 1,
+            _ if !stack.is_empty() => 2,
+            _ => 3,
+        };
+        let dst = board
+            .stacks()
+            .iter()
+            .enumerate()
+            // moving a whole stack to an empty one of the same kind changes nothing
+            .filter(|&(_, t)| !(t.is_empty() && t.role == source.role && n == source.len()))
+            .filter(|&(t, _)| board.check(Move::Cards(t, s, n)).is_ok())
+            .min_by_key(|&(_, t)| rank(t))
+            .map(|(t, _)| self.entities[idx[t]]);
+
+        if let Some(dst) = dst {
+            let action = Action::Cards { src, dst, n };
+            if self.play_actions(&[action], false, 1.0).is_ok() {
+                self.record_action(action);
             }
+        }
+    }
+
+    /// Make `actions` one after the other, or take them back last first with `undo`, and animate
+    /// the cards from where they were to where they are now, `speed` times as fast as usual.
+    pub fn play_actions(
+        &mut self,
+        actions: &[Action],
+        undo: bool,
+        speed: f32,
+    ) -> Result<(), MoveError> {
+        let flights = self.apply_actions(actions, undo)?;
 
+        // the cards are on their new stacks already, they land there again after the flight
+        let mut runs = Vec::with_capacity(flights.len());
+        for flight in flights.iter().rev() {
+            let stack = self.get_stack_mut(flight.stack).unwrap();
+            let at = stack.len() - flight.n;
+            runs.push(stack.split(at));
+        }
+        runs.reverse();
+
+        for (i, (flight, run)) in flights.into_iter().zip(runs).enumerate() {
             let ani = Animation {
-                target_pos,
-                target_stack,
-                start_delay: 0.0,
-                time_left: 0.3,
-                sound_start: Sounds::Sweep,
+                target_pos: flight.to,
+                target_stack: Some(flight.stack),
+                start_delay: 0.1 * flight.step as f32 / speed,
+                time_left: 0.3 / speed,
+                sound_start: if i == 0 { Sounds::Sweep } else { Sounds::None },
                 sound_stop: Sounds::None,
             };
-            self.animate(card, start_pos, 100.0, ani);
+            self.animate_stack(run, flight.from, 100.0 + flight.step as f32, ani);
         }
+        Ok(())
+    }
+
+    /// Make `actions` on the table through a `Board`, or take them back with `undo`. Either all
+    /// of them are made or, if one is not possible, none. Returns where the moved cards came
+    /// from and where they went.
+    pub fn apply_actions(
+        &mut self,
+        actions: &[Action],
+        undo: bool,
+    ) -> Result<Vec<Flight>, MoveError> {
+        let (mut board, idx) = self.table();
+        let mut actions = actions.to_vec();
+        if undo {
+            actions.reverse();
+        }
+
+        let mut flights = Vec::new();
+        for (step, &action) in actions.iter().enumerate() {
+            let m = self
+                .move_from_action(action, &idx)
+                .ok_or(MoveError::NoSuchStack)?;
+            let top = |board: &Board, i: usize, n: usize| {
+                let stack = &board.stacks()[i];
+                self.positions[idx[i]].unwrap() + stack.get_stackshift(&self.shifts) * n as f32
+            };
+            let len = |board: &Board, i: usize| board.stacks()[i].len();
+
+            // stacks the cards land on, how many and where they come from
+            let landings = match (m, undo) {
+                (Move::Cards(t, s, n), false) => {
+                    vec![(t, n, top(&board, s, len(&board, s).saturating_sub(n)))]
+                }
+                (Move::Cards(t, s, n), true) => {
+                    vec![(s, n, top(&board, t, len(&board, t).saturating_sub(n)))]
+                }
+                (Move::Button(_, t, sources), false) => sources
+                    .iter()
+                    .map(|&s| (t, 1, top(&board, s, len(&board, s).saturating_sub(1))))
+                    .collect(),
+                (Move::Button(_, t, sources), true) => {
+                    let first = len(&board, t).saturating_sub(4);
+                    sources
+                        .iter()
+                        .enumerate()
+                        .map(|(j, &s)| (s, 1, top(&board, t, first + j)))
+                        .collect()
+                }
+            };
+
+            if undo {
+                board.undo_move(m)?;
+            } else {
+                board.apply_move(m)?;
+            }
+
+            let mut landed = HashMap::new();
+            for &(i, n, from) in &landings {
+                let total = landings
+                    .iter()
+                    .filter(|&&(j, _, _)| j == i)
+                    .map(|&(_, n, _)| n)
+                    .sum::<usize>();
+                let before = landed.entry(i).or_insert(0);
+                let to = top(&board, i, len(&board, i) - total + *before);
+                *before += n;
+                flights.push(Flight {
+                    stack: self.entities[idx[i]],
+                    n,
+                    from,
+                    to,
+                    step,
+                });
+            }
+        }
+
+        for (stack, &i) in board.into_stacks().into_iter().zip(&idx) {
+            self.stacks[i] = Some(stack);
+        }
+        for &action in &actions {
+            if let Action::Dragons { color, .. } = action {
+                let state = if undo {
+                    ButtonState::Up
+                } else {
+                    ButtonState::Down
+                };
+                self.set_button_state(color, state);
+            }
+        }
+        self.dirty = true;
+        Ok(flights)
+    }
+
+    /// An action as a move on a board made by `table`.
+    fn move_from_action(&self, action: Action, idx: &[usize]) -> Option<Move> {
+        let i = |e: Entity| idx.iter().position(|&j| self.entities[j] == e);
+        Some(match action {
+            Action::Cards { src, dst, n } => Move::Cards(i(dst)?, i(src)?, n),
+            Action::Dragons {
+                color,
+                target,
+                sources,
+            } => Move::Button(
+                color,
+                i(target)?,
+                [
+                    i(sources[0])?,
+                    i(sources[1])?,
+                    i(sources[2])?,
+                    i(sources[3])?,
+                ],
+            ),
+        })
+    }
+
+    /// The stacks on the table as a `Board`, along with the component index of each of them.
+    fn table(&self) -> (Board, Vec<usize>) {
+        let (stacks, idx) = self
+            .stacks
+            .iter()
+            .enumerate()
+            .filter_map(|(i, stack)| match *stack {
+                Some(ref s) if s.role != StackRole::Generic && s.role != StackRole::Animation => {
+                    Some((s.clone(), i))
+                }
+                _ => None,
+            })
+            .unzip();
+        let board = Board::new(stacks)
+            .with_policy(self.automove)
+            .with_variant(self.variant);
+        (board, idx)
     }
 }
@@ -1,126 +1,152 @@
-use rand::{seq::SliceRandom, thread_rng};
-
+use board::Board;
 use cs::GameState;
+use deal;
+use layout::Layout;
+use record::Record;
+use rules::Move;
+use savegame::SaveGame;
 use types::*;
+use variant::Variant;
 
 pub struct Game {
     pub state: GameState,
+    pub seed: u64,
+    pub variant: Variant,
 
     flower_stack: Entity,
     all_stacks: Vec<Entity>,
     game_stacks: Vec<Entity>,
-    target_stacks: [Entity; 3],
-    //dragon_stacks: [Entity; 3],
 }
 
 impl Game {
-    pub fn new() -> Game {
+    pub fn new(seed: u64, variant: Variant, layout: &Layout) -> Game {
+        let mut game = Game::empty(seed, variant, layout);
+        game.animate_shuffle();
+        game
+    }
+
+    /// Resume an interrupted game. Returns `None` if the save does not fit the table layout.
+    pub fn from_save(save: &SaveGame, layout: &Layout) -> Option<Game> {
+        let mut game = Game::empty(save.seed, save.variant, layout);
+
+        if save.stacks.len() != game.all_stacks.len() {
+            return None;
+        }
+        for (&e, saved) in game.all_stacks.iter().zip(save.stacks.iter()) {
+            let stack = game.state.get_stack_mut(e).unwrap();
+            if stack.role != saved.role {
+                return None;
+            }
+            stack.cards = saved.cards.clone();
+        }
+
+        // dragons which are no longer on the table have been collapsed
+        for &color in &[Color::Red, Color::Green, Color::White] {
+            let collapsed = save
+                .stacks
+                .iter()
+                .all(|s| s.iter().all(|&c| c != Suite::Dragon(color)));
+            if collapsed {
+                game.state.set_button_state(color, ButtonState::Down);
+            }
+        }
+
+        // every step has to fit the saved table, or undoing and redoing it would break the game
+        let mut board = game.board();
+        for &m in save.history.iter().rev().flat_map(|step| step.iter().rev()) {
+            board.undo_move(m).ok()?;
+        }
+        let mut board = game.board();
+        for &m in save.undone.iter().rev().flat_map(|step| step.iter()) {
+            board.apply_move(m).ok()?;
+        }
+
+        let history = game.actions_from_moves(&save.history)?;
+        let undone = game.actions_from_moves(&save.undone)?;
+        game.state.set_history(history, undone);
+        game.state.mark_dirty();
+
+        Some(game)
+    }
+
+    /// Snapshot the game for saving. Animations in progress are completed first.
+    pub fn save(&mut self, elapsed: f32) -> SaveGame {
+        self.state.settle();
+        let history = self.moves_from_actions(self.state.history());
+        let undone = self.moves_from_actions(self.state.undone());
+        SaveGame {
+            seed: self.seed,
+            variant: self.variant,
+            elapsed,
+            stacks: self.export(),
+            history,
+            undone,
+        }
+    }
+
+    /// Every move made since the deal, for replaying the game later.
+    pub fn record(&self) -> Record {
+        // automatic moves before the first move of the player are not part of the history
+        let moves = self
+            .moves_from_actions(self.state.history())
+            .into_iter()
+            .flatten()
+            .collect();
+        Record::from_game(self.seed, self.variant, self.state.automove_policy(), moves)
+    }
+
+    fn empty(seed: u64, variant: Variant, layout: &Layout) -> Game {
         let mut state = GameState::default();
+        state.set_variant(variant);
+        state.set_shifts(layout.shifts);
+
+        for &(color, pos) in &layout.buttons {
+            state
+                .new_entity()
+                .with_position(pos)
+                .with_button(Button::new(color))
+                .build();
+        }
 
-        state
-            .new_entity()
-            .with_position(Point2::new(533.0, 54.0))
-            .with_button(Button::new(Color::Red))
-            .build();
-        state
-            .new_entity()
-            .with_position(Point2::new(533.0, 137.0))
-            .with_button(Button::new(Color::Green))
-            .build();
-        state
-            .new_entity()
-            .with_position(Point2::new(533.0, 220.0))
-            .with_button(Button::new(Color::White))
-            .build();
-
-        let r = state
-            .new_entity()
-            .with_position(Point2::new(45.0, 20.0))
-            .with_stack(Stack::new(StackRole::Dragon))
-            .build();
-        let s = state
-            .new_entity()
-            .with_position(Point2::new(197.0, 20.0))
-            .with_stack(Stack::new(StackRole::Dragon))
-            .build();
-        let t = state
-            .new_entity()
-            .with_position(Point2::new(349.0, 20.0))
-            .with_stack(Stack::new(StackRole::Dragon))
-            .build();
-        let flower_stack = state
-            .new_entity()
-            .with_position(Point2::new(614.0, 20.0))
-            .with_stack(Stack::new(StackRole::Flower))
-            .build();
-        let x = state
-            .new_entity()
-            .with_position(Point2::new(805.0, 20.0))
-            .with_stack(Stack::new(StackRole::Target))
-            .build();
-        let y = state
-            .new_entity()
-            .with_position(Point2::new(957.0, 20.0))
-            .with_stack(Stack::new(StackRole::Target))
-            .build();
-        let z = state
-            .new_entity()
-            .with_position(Point2::new(1109.0, 20.0))
-            .with_stack(Stack::new(StackRole::Target))
-            .build();
-        let a = state
-            .new_entity()
-            .with_position(Point2::new(45.0, 283.0))
-            .with_stack(Stack::new(StackRole::Sorting))
-            .build();
-        let b = state
-            .new_entity()
-            .with_position(Point2::new(197.0, 283.0))
-            .with_stack(Stack::new(StackRole::Sorting))
-            .build();
-        let c = state
-            .new_entity()
-            .with_position(Point2::new(349.0, 283.0))
-            .with_stack(Stack::new(StackRole::Sorting))
-            .build();
-        let d = state
-            .new_entity()
-            .with_position(Point2::new(501.0, 283.0))
-            .with_stack(Stack::new(StackRole::Sorting))
-            .build();
-        let e = state
-            .new_entity()
-            .with_position(Point2::new(653.0, 283.0))
-            .with_stack(Stack::new(StackRole::Sorting))
-            .build();
-        let f = state
-            .new_entity()
-            .with_position(Point2::new(805.0, 283.0))
-            .with_stack(Stack::new(StackRole::Sorting))
-            .build();
-        let g = state
-            .new_entity()
-            .with_position(Point2::new(957.0, 283.0))
-            .with_stack(Stack::new(StackRole::Sorting))
-            .build();
-        let h = state
-            .new_entity()
-            .with_position(Point2::new(1109.0, 283.0))
-            .with_stack(Stack::new(StackRole::Sorting))
-            .build();
-
-        let mut game = Game {
-            state,
-            flower_stack,
-            all_stacks: vec![a, b, c, d, e, f, g, h, r, s, t, flower_stack, x, y, z],
-            game_stacks: vec![a, b, c, d, e, f, g, h],
-            target_stacks: [x, y, z],
-            //dragon_stacks: [r, s, t],
+        let table = variant.table();
+        let mut stacks = |role| {
+            let n = table.iter().filter(|&&r| r == role).count();
+            layout
+                .stacks(role)
+                .take(n)
+                .map(|pos| {
+                    state
+                        .new_entity()
+                        .with_position(pos)
+                        .with_stack(Stack::new(role))
+                        .build()
+                })
+                .collect::<Vec<_>>()
         };
 
-        game.animate_shuffle();
+        let dragon_stacks = stacks(StackRole::Dragon);
+        let flower_stack = stacks(StackRole::Flower)[0];
+        let target_stacks = stacks(StackRole::Target);
+        let game_stacks = stacks(StackRole::Sorting);
 
-        game
+        // the same order as `Variant::table`
+        let mut all_stacks = game_stacks.clone();
+        all_stacks.extend(dragon_stacks);
+        all_stacks.push(flower_stack);
+        all_stacks.extend(target_stacks);
+
+        Game {
+            state,
+            seed,
+            variant,
+            flower_stack,
+            all_stacks,
+            game_stacks,
+        }
+    }
+
+    pub fn board(&self) -> Board {
+        Board::new(self.export()).with_variant(self.variant)
     }
 
     pub fn export<'a>(&'a self) -> Vec<Stack> {
@@ -130,47 +156,74 @@ impl Game {
             .collect()
     }
 
-    pub fn check_win_condition(&self) -> bool {
-        self.game_stacks
-            .iter()
-            .all(|&s| self.state.get_stack(s).unwrap().len() == 0)
-            && self
-                .target_stacks
-                .iter()
-                .all(|&s| self.state.get_stack(s).unwrap().len() == 9)
+    /// Translate a move on the exported stacks into an action on the stack entities.
+    pub fn action_from_move(&self, m: Move) -> Option<Action> {
+        let e = |i: usize| self.all_stacks.get(i).cloned();
+        let action = match m {
+            Move::Cards(t, s, n) => Action::Cards {
+                src: e(s)?,
+                dst: e(t)?,
+                n,
+            },
+            Move::Button(color, t, s) => Action::Dragons {
+                color,
+                target: e(t)?,
+                sources: [e(s[0])?, e(s[1])?, e(s[2])?, e(s[3])?],
+            },
+        };
+        Some(action)
     }
 
-    pub fn shuffled_deck() -> Stack {
-        let mut cards = Vec::with_capacity(40);
-
-        for _ in 0..4 {
-            cards.push(Suite::Dragon(Color::Red));
-            cards.push(Suite::Dragon(Color::Green));
-            cards.push(Suite::Dragon(Color::White));
+    /// Translate an action on the stack entities into a move on the exported stacks.
+    pub fn move_from_action(&self, action: Action) -> Move {
+        let i = |e: Entity| self.all_stacks.iter().position(|&s| s == e).unwrap();
+        match action {
+            Action::Cards { src, dst, n } => Move::Cards(i(dst), i(src), n),
+            Action::Dragons {
+                color,
+                target,
+                sources,
+            } => Move::Button(
+                color,
+                i(target),
+                [i(sources[0]), i(sources[1]), i(sources[2]), i(sources[3])],
+            ),
         }
+    }
 
-        for i in 1..10 {
-            cards.push(Suite::Number(i, Color::Red));
-            cards.push(Suite::Number(i, Color::Green));
-            cards.push(Suite::Number(i, Color::White));
-        }
+    /// Translate a solution found by the solver on the exported stacks into actions.
+    pub fn solution_actions(&self, moves: &[Move]) -> Option<Vec<Action>> {
+        moves.iter().map(|&m| self.action_from_move(m)).collect()
+    }
 
-        cards.push(Suite::Flower);
+    fn actions_from_moves(&self, steps: &[Vec<Move>]) -> Option<Vec<Vec<Action>>> {
+        steps
+            .iter()
+            .map(|step| step.iter().map(|&m| self.action_from_move(m)).collect())
+            .collect()
+    }
 
-        cards.shuffle(&mut thread_rng());
+    fn moves_from_actions(&self, steps: &[Vec<Action>]) -> Vec<Vec<Move>> {
+        steps
+            .iter()
+            .map(|step| step.iter().map(|&a| self.move_from_action(a)).collect())
+            .collect()
+    }
 
-        Stack {
-            cards,
-            role: StackRole::Generic,
-        }
+    pub fn check_win_condition(&self) -> bool {
+        self.board().is_won()
     }
 
     pub fn animate_shuffle(&mut self) {
         let f = self.flower_stack;
         let target_stack = Some(f);
         let stack_pos = *self.state.get_position(f).unwrap();
-        let shift = self.state.get_stack(f).unwrap().get_stackshift();
-        for n in 0..40 {
+        let shift = self
+            .state
+            .get_stack(f)
+            .unwrap()
+            .get_stackshift(self.state.shifts());
+        for n in 0..self.variant.deck().len() {
             let i = 1.0 + 0.1 * (n as f32);
             let start_pos = stack_pos - shift * i * (stack_pos.y + CARD_HEIGHT) / shift.y;
             let target_pos = stack_pos + shift * n as f32;
@@ -199,14 +252,14 @@ impl Game {
             .cards
             .clear();
 
-        let mut new_deck = Game::shuffled_deck(); // TODO: should this be a parameter to the function?
+        let mut new_deck = deal::shuffled_deck(self.seed, self.variant);
 
         let fpos = *self.state.get_position(self.flower_stack).unwrap();
         let fshift = self
             .state
             .get_stack(self.flower_stack)
             .unwrap()
-            .get_stackshift();
+            .get_stackshift(self.state.shifts());
 
         let mut height = 0.0;
         let mut s = 0;
@@ -214,7 +267,11 @@ impl Game {
         let mut start_delay = 0.0;
         while let Some(card) = new_deck.pop_card() {
             let target_stack = self.game_stacks[s];
-            let shift = self.state.get_stack(target_stack).unwrap().get_stackshift();
+            let shift = self
+                .state
+                .get_stack(target_stack)
+                .unwrap()
+                .get_stackshift(self.state.shifts());
             let target_pos = *self.state.get_position(target_stack).unwrap() + shift * height;
 
             let start_pos = fpos + fshift * height;
@@ -240,7 +297,9 @@ impl Game {
         }
     }
 
-    pub fn animate_giveup(&mut self) {
+    /// Let all cards fly off the table, away from its center.
+    pub fn animate_giveup(&mut self, layout: &Layout) {
+        let center = Point2::origin() + layout.size / 2.0;
         let mut cards = Vec::with_capacity(40);
 
         for &e in self.state.iter() {
@@ -255,7 +314,7 @@ impl Game {
             };
 
             for (i, &card) in stack.iter().enumerate() {
-                let cardpos = pos + stack.get_stackshift() * i as f32;
+                let cardpos = pos + stack.get_stackshift(self.state.shifts()) * i as f32;
                 cards.push((card, cardpos));
             }
         }
@@ -263,7 +322,7 @@ impl Game {
         self.state.clear();
 
         for (z, (card, start_pos)) in cards.into_iter().enumerate() {
-            let mut direction = start_pos - Point2::new(640.0, 400.0);
+            let mut direction = start_pos - center;
             let dist = direction.norm();
             direction = direction / dist;
 
@@ -283,6 +342,7 @@ impl Game {
 
     pub fn animate_victory(&mut self) {
         let mut cards = Vec::with_capacity(40);
+        let shifts = *self.state.shifts();
 
         loop {
             let mut empty = true;
@@ -291,7 +351,7 @@ impl Game {
                 let stack = self.state.get_stack_mut(e).unwrap();
                 if let Some(card) = stack.pop_card() {
                     empty = false;
-                    let cardpos = pos + stack.get_stackshift() * stack.len() as f32;
+                    let cardpos = pos + stack.get_stackshift(&shifts) * stack.len() as f32;
                     cards.push((card, cardpos));
                     continue;
                 }
@@ -7,6 +7,7 @@ use game::Game;
 use resources::Resources;
 
 use super::main_state::MainState;
+use super::replay_state::ReplayState;
 use super::victory_state::VictoryState;
 use super::GameWrapper;
 
@@ -34,16 +35,22 @@ impl EventHandler for GiveupState {
     }
 
     fn draw(&mut self, ctx: &mut Context) -> GameResult<()> {
-        self.game.state.run_render(ctx, &mut self.resources)?;
+        self.game
+            .state
+            .run_render(ctx, &mut self.resources, self.game.seed)?;
 
         graphics::present(ctx)?;
         Ok(())
     }
+
+    fn resize_event(&mut self, ctx: &mut Context, width: f32, height: f32) {
+        self.resources.viewport.resize(ctx, width, height);
+    }
 }
 
 impl From<MainState> for GiveupState {
     fn from(mut old: MainState) -> GiveupState {
-        old.game.animate_giveup();
+        old.game.animate_giveup(&old.resources.layout);
         GiveupState {
             resources: old.resources,
             game: old.game,
@@ -53,7 +60,17 @@ impl From<MainState> for GiveupState {
 
 impl From<VictoryState> for GiveupState {
     fn from(mut old: VictoryState) -> GiveupState {
-        old.game.animate_giveup();
+        old.game.animate_giveup(&old.resources.layout);
+        GiveupState {
+            resources: old.resources,
+            game: old.game,
+        }
+    }
+}
+
+impl From<ReplayState> for GiveupState {
+    fn from(mut old: ReplayState) -> GiveupState {
+        old.game.animate_giveup(&old.resources.layout);
         GiveupState {
             resources: old.resources,
             game: old.game,
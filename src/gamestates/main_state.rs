@@ -1,33 +1,129 @@
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+use std::time::Duration;
+
 use ggez::event::{EventHandler, KeyCode, KeyMods, MouseButton};
 use ggez::graphics;
+use ggez::graphics::DrawParam;
 use ggez::timer;
 use ggez::{Context, GameResult};
 
 use game::Game;
 use resources::Resources;
+use savegame::SaveGame;
+use storage::{RecordFile, SaveGameFile};
+use types::*;
 
-use ai::{AiResult, AiState};
+use ai::{AiResult, AiState, Budget};
 
 use super::welcome_state::WelcomeState;
 use super::GameWrapper;
 
+/// How quickly and close to each other two clicks make a double click, in seconds and pixels.
+const DOUBLE_CLICK_TIME: f32 = 0.4;
+const DOUBLE_CLICK_DISTANCE: f32 = 8.0;
+
 pub struct MainState {
     pub resources: Resources,
     pub game: Game,
     win_counted: bool,
+    elapsed: f32,
+    closed: bool,
+
+    /// The board the solver is working on, and where its answer arrives.
+    hint: Option<(Vec<Stack>, Receiver<AiResult>)>,
+    /// A short message at the bottom of the window, and how long it stays there.
+    notice: Option<(String, f32)>,
 
     last_x: f32,
     last_y: f32,
+    /// Where and when the last left click was, to notice double clicks.
+    last_click: Option<(Point2, f32)>,
 }
 
 impl MainState {
     pub fn next_state(self) -> GameWrapper {
-        if self.game.check_win_condition() {
+        if self.closed {
+            GameWrapper::Quit
+        } else if self.game.check_win_condition() {
             GameWrapper::Victory(self.into())
         } else {
             GameWrapper::GiveUp(self.into())
         }
     }
+
+    fn store_record(&mut self, ctx: &mut Context) {
+        self.game.state.settle();
+        if let Err(e) = self.game.record().store(ctx) {
+            error!("Could not save the game record: {}", e);
+        }
+    }
+
+    /// Look for a winning line from the current position without blocking the game. Only one
+    /// search runs at a time, the solver can not be stopped halfway.
+    fn request_hint(&mut self) {
+        if self.game.state.busy() || self.hint.is_some() {
+            return;
+        }
+        let board = self.game.export();
+        let ai = match AiState::new(&board) {
+            Some(ai) => ai
+                .with_policy(self.resources.settings.automove)
+                .with_variant(self.game.variant),
+            None => return,
+        };
+        let (tx, rx) = mpsc::channel();
+        thread::spawn(move || {
+            let budget = Budget {
+                time: Some(Duration::from_secs(5)),
+                ..Budget::iterations(200_000)
+            };
+            // the game may have moved on and dropped the receiver
+            let _ = tx.send(ai.astar(budget));
+        });
+        self.hint = Some((board, rx));
+    }
+
+    fn is_double_click(&mut self, pos: Point2) -> bool {
+        let double = match self.last_click {
+            Some((p, time)) => {
+                self.elapsed - time < DOUBLE_CLICK_TIME && (pos - p).norm() < DOUBLE_CLICK_DISTANCE
+            }
+            None => false,
+        };
+        // a third click starts over
+        self.last_click = if double {
+            None
+        } else {
+            Some((pos, self.elapsed))
+        };
+        double
+    }
+
+    fn poll_hint(&mut self) {
+        let result = match self.hint {
+            Some((_, ref rx)) => match rx.try_recv() {
+                Ok(result) => result,
+                Err(TryRecvError::Empty) => return,
+                Err(TryRecvError::Disconnected) => AiResult::Unknown,
+            },
+            None => return,
+        };
+        let (board, _) = self.hint.take().unwrap();
+
+        // the answer is useless if cards were moved in the meantime
+        if board != self.game.export() {
+            return;
+        }
+        let action = match result {
+            AiResult::Winable(moves) => moves.first().and_then(|&m| self.game.action_from_move(m)),
+            AiResult::Unknown | AiResult::Lost => None,
+        };
+        match action {
+            Some(action) => self.game.state.show_hint(action),
+            None => self.notice = Some(("No winning line found.".to_string(), 3.0)),
+        }
+    }
 }
 
 impl EventHandler for MainState {
@@ -39,11 +135,23 @@ impl EventHandler for MainState {
 
         let dt = timer::duration_to_f64(timer::delta(ctx)) as f32;
         self.game.state.run_update(dt, &mut self.resources);
+        self.elapsed += dt;
+        self.poll_hint();
+        if let Some(error) = self.game.state.take_drop_error() {
+            self.notice = Some((error.to_string(), 2.0));
+        }
+        self.notice = self
+            .notice
+            .take()
+            .map(|(notice, time)| (notice, time - dt))
+            .filter(|&(_, time)| time > 0.0);
 
         if self.game.check_win_condition() {
             if !self.win_counted {
                 self.resources.add_win(ctx);
                 self.win_counted = true;
+                SaveGame::delete(ctx);
+                self.store_record(ctx);
             }
             ggez::event::quit(ctx);
         }
@@ -52,34 +160,66 @@ impl EventHandler for MainState {
     }
 
     fn draw(&mut self, ctx: &mut Context) -> GameResult<()> {
-        self.game.state.run_render(ctx, &mut self.resources)?;
+        self.game
+            .state
+            .run_render(ctx, &mut self.resources, self.game.seed)?;
+
+        if let Some((ref notice, _)) = self.notice {
+            let table = self.resources.layout.size;
+            let text = self.resources.get_text(ctx, notice)?;
+            let pos = Point2::from([
+                table.x / 2.0 - text.width(ctx) as f32 / 2.0,
+                table.y - text.height(ctx) as f32,
+            ]);
+            graphics::draw(
+                ctx,
+                text,
+                DrawParam::new()
+                    .dest(pos)
+                    .color(graphics::Color::new(0.0, 0.0, 0.0, 1.0)),
+            )?;
+        }
+
         graphics::present(ctx)?;
         Ok(())
     }
 
-    fn mouse_button_down_event(
-        &mut self,
-        _ctx: &mut Context,
-        _button: MouseButton,
-        x: f32,
-        y: f32,
-    ) {
-        self.game
-            .state
-            .handle_mouse_button_down(x, y, &mut self.resources);
+    fn resize_event(&mut self, ctx: &mut Context, width: f32, height: f32) {
+        self.resources.viewport.resize(ctx, width, height);
     }
 
-    fn mouse_button_up_event(&mut self, _ctx: &mut Context, _button: MouseButton, x: f32, y: f32) {
-        self.game
-            .state
-            .handle_mouse_button_up(x, y, &mut self.resources);
+    fn mouse_button_down_event(&mut self, _ctx: &mut Context, button: MouseButton, x: f32, y: f32) {
+        let pos = self.resources.viewport.to_table(x, y);
+        match button {
+            MouseButton::Right => self.game.state.handle_auto_send(pos.x, pos.y),
+            MouseButton::Left if self.is_double_click(pos) => {
+                self.game.state.handle_auto_send(pos.x, pos.y)
+            }
+            MouseButton::Left => {
+                self.game
+                    .state
+                    .handle_mouse_button_down(pos.x, pos.y, &mut self.resources)
+            }
+            _ => {}
+        }
+    }
+
+    fn mouse_button_up_event(&mut self, _ctx: &mut Context, button: MouseButton, x: f32, y: f32) {
+        // only the left button drags cards
+        if button == MouseButton::Left {
+            let pos = self.resources.viewport.to_table(x, y);
+            self.game
+                .state
+                .handle_mouse_button_up(pos.x, pos.y, &mut self.resources);
+        }
     }
 
     fn mouse_motion_event(&mut self, _ctx: &mut Context, x: f32, y: f32, _xrel: f32, _yrel: f32) {
-        let xrel = x - self.last_x;
-        let yrel = y - self.last_y;
-        self.last_x = x;
-        self.last_y = y;
+        let pos = self.resources.viewport.to_table(x, y);
+        let xrel = pos.x - self.last_x;
+        let yrel = pos.y - self.last_y;
+        self.last_x = pos.x;
+        self.last_y = pos.y;
         self.game.state.handle_mouse_move(xrel, yrel);
     }
 
@@ -87,33 +227,79 @@ impl EventHandler for MainState {
         &mut self,
         ctx: &mut Context,
         keycode: KeyCode,
-        _keymod: KeyMods,
+        keymod: KeyMods,
         _repeat: bool,
     ) {
+        let ctrl = keymod.contains(KeyMods::CTRL);
+        let shift = keymod.contains(KeyMods::SHIFT);
+        if !ctrl && self.game.state.handle_key(keycode, &mut self.resources) {
+            return;
+        }
         match keycode {
-            KeyCode::Escape => ggez::event::quit(ctx),
+            KeyCode::Escape => {
+                SaveGame::delete(ctx);
+                self.store_record(ctx);
+                ggez::event::quit(ctx)
+            }
+            KeyCode::Z if ctrl && shift => self.game.state.handle_redo(1.0),
+            KeyCode::Z if ctrl => self.game.state.handle_undo(1.0),
+            KeyCode::Y if ctrl => self.game.state.handle_redo(1.0),
+            KeyCode::H => self.request_hint(),
             KeyCode::Back => {
-                let ai = AiState::new(self.game.export());
-                match ai.astar(10000) {
+                let policy = self.resources.settings.automove;
+                let variant = self.game.variant;
+                let ai = AiState::new(&self.game.export())
+                    .map(|ai| ai.with_policy(policy).with_variant(variant));
+                match ai.map_or(AiResult::Unknown, |ai| ai.astar(Budget::iterations(10000))) {
                     AiResult::Unknown => println!("?"),
-                    AiResult::Winable(n) => println!("{} :-)", n),
+                    AiResult::Winable(moves) => {
+                        println!("{} :-)", moves.len());
+                        for action in self.game.solution_actions(&moves).unwrap_or_default() {
+                            debug!("{:?}", action);
+                        }
+                    }
                     AiResult::Lost => println!(":-("),
                 }
             }
             _ => {}
         }
     }
+
+    fn quit_event(&mut self, ctx: &mut Context) -> bool {
+        if !self.game.check_win_condition() {
+            let save = self.game.save(self.elapsed);
+            if let Err(e) = save.store(ctx) {
+                error!("Could not save the game: {}", e);
+            }
+        }
+        self.closed = true;
+        false
+    }
 }
 
 impl From<WelcomeState> for MainState {
     fn from(mut old: WelcomeState) -> MainState {
-        old.game.animate_deal();
+        let elapsed = match old.resumed {
+            Some(elapsed) => elapsed,
+            None => {
+                old.game.animate_deal();
+                0.0
+            }
+        };
+        old.game
+            .state
+            .set_automove_policy(old.resources.settings.automove);
         MainState {
             resources: old.resources,
             game: old.game,
             win_counted: false,
+            elapsed,
+            closed: false,
+            hint: None,
+            notice: None,
             last_x: 0.0,
             last_y: 0.0,
+            last_click: None,
         }
     }
 }
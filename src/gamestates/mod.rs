@@ -1,5 +1,6 @@
 mod giveup_state;
 mod main_state;
+mod replay_state;
 mod victory_state;
 mod welcome_state;
 
@@ -8,8 +9,11 @@ use std::fmt;
 use ggez::event;
 use ggez::{Context, GameResult};
 
+use record::Record;
+
 use self::giveup_state::GiveupState;
 use self::main_state::MainState;
+use self::replay_state::ReplayState;
 use self::victory_state::VictoryState;
 use self::welcome_state::WelcomeState;
 
@@ -18,6 +22,7 @@ pub enum GameWrapper {
     Game(MainState),
     Victory(VictoryState),
     GiveUp(GiveupState),
+    Replay(ReplayState),
     Quit,
 }
 
@@ -25,8 +30,12 @@ use self::GameWrapper::*;
 use ggez::event::EventsLoop;
 
 impl GameWrapper {
-    pub fn new(ctx: &mut Context) -> GameResult<Self> {
-        Ok(GameWrapper::Welcome(WelcomeState::new(ctx)?))
+    pub fn new(ctx: &mut Context, seed: Option<u64>) -> GameResult<Self> {
+        Ok(GameWrapper::Welcome(WelcomeState::new(ctx, seed)?))
+    }
+
+    pub fn replay(ctx: &mut Context, record: &Record) -> GameResult<Self> {
+        Ok(GameWrapper::Replay(ReplayState::new(ctx, record)?))
     }
 
     pub fn run(self, ctx: &mut Context, events_loop: &mut EventsLoop) -> GameResult<Self> {
@@ -50,6 +59,10 @@ impl GameWrapper {
                 event::run(ctx, events_loop, &mut state)?;
                 Ok(state.next_state())
             }
+            Replay(mut state) => {
+                event::run(ctx, events_loop, &mut state)?;
+                Ok(state.next_state())
+            }
             Quit => panic!("Invalid Game State"),
         }
     }
@@ -62,6 +75,7 @@ impl fmt::Display for GameWrapper {
             Game(_) => "Game",
             Victory(_) => "Victory",
             GiveUp(_) => "GiveUp",
+            Replay(_) => "Replay",
             Quit => "Quit",
         };
         write!(f, "{}", name)
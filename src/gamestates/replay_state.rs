@@ -0,0 +1,185 @@
+use ggez::event::*;
+use ggez::graphics;
+use ggez::graphics::DrawParam;
+use ggez::timer;
+use ggez::{Context, GameResult};
+
+use board::Board;
+use game::Game;
+use record::Record;
+use resources::Resources;
+use rules::AutomovePolicy;
+use types::*;
+
+use super::GameWrapper;
+
+/// Moves per second at normal speed. The cards fly faster at higher speeds, so they keep up.
+const BASE_SPEED: f32 = 2.0;
+
+/// Plays back a recorded game. Moves that were played are in the history of the game state and
+/// the ones still to come are in its redo list, so stepping uses the undo and redo animations.
+pub struct ReplayState {
+    pub resources: Resources,
+    pub game: Game,
+    total: usize,
+    playing: bool,
+    /// factor on `BASE_SPEED`
+    speed: f32,
+    /// time until the next move is played
+    wait: f32,
+    ready: bool,
+    closed: bool,
+}
+
+impl ReplayState {
+    pub fn new(ctx: &mut Context, record: &Record) -> GameResult<ReplayState> {
+        let resources = Resources::new(ctx)?;
+        let mut game = Game::new(record.seed, record.variant, &resources.layout);
+        game.animate_deal();
+        game.state.set_automove_policy(AutomovePolicy::Off);
+
+        // the first move is made on the dealt cards, and nothing moves on its own in between
+        let mut board = Board::deal(record.seed, record.variant);
+        let moves = record
+            .moves
+            .iter()
+            .cloned()
+            .take_while(|&m| board.apply_move(m).is_ok())
+            .collect::<Vec<_>>();
+        if moves.len() < record.moves.len() {
+            warn!(
+                "The record does not fit the deal after {} moves, replaying only these",
+                moves.len()
+            );
+        }
+
+        // every move is a step of its own, the first one on top
+        let steps = match game.solution_actions(&moves) {
+            Some(actions) => actions.into_iter().rev().map(|a| vec![a]).collect(),
+            None => Vec::new(),
+        };
+        game.state.set_history(Vec::new(), steps);
+
+        Ok(ReplayState {
+            resources,
+            game,
+            total: moves.len(),
+            playing: true,
+            speed: 1.0,
+            wait: 0.0,
+            ready: false,
+            closed: false,
+        })
+    }
+
+    pub fn next_state(self) -> GameWrapper {
+        if self.closed {
+            GameWrapper::Quit
+        } else {
+            GameWrapper::GiveUp(self.into())
+        }
+    }
+
+    fn played(&self) -> usize {
+        self.game.state.history().len()
+    }
+
+    fn step_forward(&mut self) {
+        self.game.state.handle_redo(self.speed);
+        self.wait = 1.0 / (BASE_SPEED * self.speed);
+    }
+}
+
+impl EventHandler for ReplayState {
+    fn update(&mut self, ctx: &mut Context) -> GameResult<()> {
+        if !self.ready {
+            // skip first frame because it has a super high delta-time
+            self.ready = true;
+            return Ok(());
+        }
+
+        let dt = timer::duration_to_f64(timer::delta(ctx)) as f32;
+        self.game.state.run_update(dt, &mut self.resources);
+
+        if self.played() == self.total {
+            self.playing = false;
+        }
+        if self.playing {
+            self.wait -= dt;
+            if self.wait <= 0.0 && !self.game.state.busy() {
+                self.step_forward();
+            }
+        }
+
+        Ok(())
+    }
+
+    fn draw(&mut self, ctx: &mut Context) -> GameResult<()> {
+        self.game
+            .state
+            .run_render(ctx, &mut self.resources, self.game.seed)?;
+
+        let txt = if self.playing {
+            format!(
+                "Move {}/{}, playing at {}x",
+                self.played(),
+                self.total,
+                self.speed
+            )
+        } else {
+            format!("Move {}/{}, paused", self.played(), self.total)
+        };
+        let table = self.resources.layout.size;
+        let text = self.resources.get_text(ctx, &txt)?;
+        let pos = Point2::new(
+            table.x / 2.0 - text.width(ctx) as f32 / 2.0,
+            table.y - text.height(ctx) as f32,
+        );
+        graphics::draw(
+            ctx,
+            text,
+            DrawParam::new()
+                .dest(pos)
+                .color(graphics::Color::new(0.0, 0.0, 0.0, 1.0)),
+        )?;
+
+        graphics::present(ctx)?;
+        Ok(())
+    }
+
+    fn resize_event(&mut self, ctx: &mut Context, width: f32, height: f32) {
+        self.resources.viewport.resize(ctx, width, height);
+    }
+
+    fn key_down_event(
+        &mut self,
+        ctx: &mut Context,
+        keycode: KeyCode,
+        _keymod: KeyMods,
+        _repeat: bool,
+    ) {
+        match keycode {
+            KeyCode::Escape => ggez::event::quit(ctx),
+            KeyCode::Space => {
+                self.playing = !self.playing && self.played() < self.total;
+                self.wait = 0.0;
+            }
+            KeyCode::Right => {
+                self.playing = false;
+                self.step_forward();
+            }
+            KeyCode::Left => {
+                self.playing = false;
+                self.game.state.handle_undo(self.speed);
+            }
+            KeyCode::Up => self.speed = (self.speed * 2.0).min(8.0),
+            KeyCode::Down => self.speed = (self.speed / 2.0).max(0.25),
+            _ => {}
+        }
+    }
+
+    fn quit_event(&mut self, _ctx: &mut Context) -> bool {
+        self.closed = true;
+        false
+    }
+}
@@ -36,12 +36,15 @@ impl EventHandler for VictoryState {
     }
 
     fn draw(&mut self, ctx: &mut Context) -> GameResult<()> {
-        self.game.state.run_render(ctx, &mut self.resources)?;
+        self.game
+            .state
+            .run_render(ctx, &mut self.resources, self.game.seed)?;
 
+        let table = self.resources.layout.size;
         let text = self.resources.get_text(ctx, "Congratulations.")?;
         let pos = Point2::from([
-            640.0 - text.width(ctx) as f32 / 2.0,
-            403.0 - text.height(ctx) as f32 / 2.0,
+            table.x / 2.0 - text.width(ctx) as f32 / 2.0,
+            table.y / 2.0 - text.height(ctx) as f32 / 2.0,
         ]);
         graphics::draw(ctx, text, DrawParam::new().dest(pos))?;
 
@@ -49,6 +52,10 @@ impl EventHandler for VictoryState {
         Ok(())
     }
 
+    fn resize_event(&mut self, ctx: &mut Context, width: f32, height: f32) {
+        self.resources.viewport.resize(ctx, width, height);
+    }
+
     fn mouse_button_down_event(
         &mut self,
         ctx: &mut Context,
@@ -1,3 +1,9 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
 use ggez::event::*;
 use ggez::graphics;
 use ggez::mint::Point2;
@@ -8,25 +14,186 @@ use super::giveup_state::GiveupState;
 use super::victory_state::VictoryState;
 use super::GameWrapper;
 
+use ai::{AiState, Budget, Difficulty};
+use deal;
 use game::Game;
 use ggez::graphics::DrawParam;
 use resources::Resources;
+use rules::AutomovePolicy;
+use savegame::SaveGame;
+use storage::SaveGameFile;
+use variant::Variant;
 
 pub struct WelcomeState {
     pub resources: Resources,
     pub move_on: bool,
     pub game: Game,
     pub ready: bool,
+    /// an interrupted game that can be resumed
+    pub saved: Option<SaveGame>,
+    /// elapsed time of the saved game if the player chose to resume it
+    pub resumed: Option<f32>,
+    /// the solver rating the deal or looking for a new one in the background
+    deal_search: Option<Receiver<(u64, Option<Difficulty>)>>,
+    /// set to stop the search for a new deal
+    search_cancel: Arc<AtomicBool>,
+    /// the solver is looking for a deal that fits the settings, which replaces the current one
+    replaces_deal: bool,
+    /// difficulty of the current deal, if the solver could rate it
+    rating: Option<Difficulty>,
+    /// the player wants to start as soon as a fitting deal is found
+    waiting: bool,
+    /// the deal was chosen on the command line
+    fixed_seed: bool,
 }
 
 impl WelcomeState {
-    pub fn new(ctx: &mut Context) -> GameResult<Self> {
-        Ok(WelcomeState {
-            resources: Resources::new(ctx)?,
+    pub fn new(ctx: &mut Context, seed: Option<u64>) -> GameResult<Self> {
+        // a deal requested on the command line takes precedence over the saved game
+        let resources = Resources::new(ctx)?;
+        let saved = match seed {
+            Some(_) => None,
+            None => SaveGame::load(ctx)?
+                .filter(|save| Game::from_save(save, &resources.layout).is_some()),
+        };
+        let mut state = WelcomeState::with_resources(resources, seed);
+        state.ready = false;
+        state.saved = saved;
+        Ok(state)
+    }
+
+    fn with_resources(resources: Resources, seed: Option<u64>) -> WelcomeState {
+        let variant = resources.settings.variant;
+        let game = Game::new(
+            seed.unwrap_or_else(deal::random_seed),
+            variant,
+            &resources.layout,
+        );
+        let mut state = WelcomeState {
+            resources,
             move_on: false,
-            game: Game::new(),
-            ready: false,
-        })
+            game,
+            ready: true,
+            saved: None,
+            resumed: None,
+            deal_search: None,
+            search_cancel: Arc::new(AtomicBool::new(false)),
+            replaces_deal: false,
+            rating: None,
+            waiting: false,
+            fixed_seed: seed.is_some(),
+        };
+        state.analyze_deal();
+        state
+    }
+
+    /// Let the solver rate the deal in the background. If the settings ask for a winnable deal
+    /// or one of a certain difficulty, it looks for a fitting deal to replace the current one.
+    fn analyze_deal(&mut self) {
+        let settings = &self.resources.settings;
+        let wanted = settings.difficulty;
+        let search = (settings.winnable_only || wanted.is_some()) && !self.fixed_seed;
+        let seed = self.game.seed;
+        let variant = self.game.variant;
+
+        self.stop_search();
+        let cancel = Arc::clone(&self.search_cancel);
+        let (tx, rx) = mpsc::channel();
+        thread::spawn(move || {
+            let budget = Budget {
+                time: Some(Duration::from_secs(2)),
+                ..Budget::iterations(100_000)
+            };
+            let result = if search {
+                match deal::find_seed(budget, wanted, variant, &cancel) {
+                    Some((seed, difficulty)) => (seed, Some(difficulty)),
+                    None => return,
+                }
+            } else {
+                let rating = AiState::new(&deal::deal(seed, variant))
+                    .and_then(|ai| ai.with_variant(variant).rate(budget));
+                (seed, rating)
+            };
+            // the player may have changed the settings in the meantime
+            let _ = tx.send(result);
+        });
+        self.deal_search = Some(rx);
+        self.replaces_deal = search;
+        self.rating = None;
+    }
+
+    /// Stop the solver looking for a new deal, and forget about the rating in progress.
+    fn stop_search(&mut self) {
+        self.search_cancel.store(true, Ordering::Relaxed);
+        self.search_cancel = Arc::new(AtomicBool::new(false));
+        self.deal_search = None;
+    }
+
+    fn start(&mut self, ctx: &mut Context) {
+        if self.deal_search.is_some() && self.replaces_deal {
+            self.waiting = true;
+        } else {
+            self.stop_search();
+            self.move_on = true;
+            ggez::event::quit(ctx);
+        }
+    }
+
+    /// Stop waiting for a fitting deal and keep the current one.
+    fn cancel_waiting(&mut self) {
+        self.stop_search();
+        self.waiting = false;
+    }
+
+    fn toggle_winnable_only(&mut self, ctx: &mut Context) {
+        let winnable_only = !self.resources.settings.winnable_only;
+        self.resources.settings.winnable_only = winnable_only;
+        self.store_settings(ctx);
+        self.analyze_deal();
+    }
+
+    /// Cycle through the difficulties a new deal can be asked for.
+    fn cycle_difficulty(&mut self, ctx: &mut Context) {
+        let difficulty = match self.resources.settings.difficulty {
+            None => Some(Difficulty::Easy),
+            Some(Difficulty::Easy) => Some(Difficulty::Medium),
+            Some(Difficulty::Medium) => Some(Difficulty::Hard),
+            Some(Difficulty::Hard) => Some(Difficulty::Expert),
+            Some(Difficulty::Expert) => None,
+        };
+        self.resources.settings.difficulty = difficulty;
+        self.store_settings(ctx);
+        self.analyze_deal();
+    }
+
+    fn cycle_automove(&mut self, ctx: &mut Context) {
+        let automove = match self.resources.settings.automove {
+            AutomovePolicy::Off => AutomovePolicy::Safe,
+            AutomovePolicy::Safe => AutomovePolicy::Aggressive,
+            AutomovePolicy::Aggressive => AutomovePolicy::Off,
+        };
+        self.resources.settings.automove = automove;
+        self.store_settings(ctx);
+    }
+
+    /// Cycle through the variants and deal the current seed again with the new rules.
+    fn cycle_variant(&mut self, ctx: &mut Context) {
+        let all = &Variant::ALL;
+        let i = all
+            .iter()
+            .position(|&v| v == self.game.variant)
+            .unwrap_or(0);
+        let variant = all[(i + 1) % all.len()];
+        self.resources.settings.variant = variant;
+        self.store_settings(ctx);
+        self.game = Game::new(self.game.seed, variant, &self.resources.layout);
+        self.analyze_deal();
+    }
+
+    fn store_settings(&mut self, ctx: &mut Context) {
+        if let Err(e) = self.resources.settings.store(ctx) {
+            error!("Could not save the settings: {}", e);
+        }
     }
 
     pub fn next_state(self) -> GameWrapper {
@@ -48,25 +215,95 @@ impl EventHandler for WelcomeState {
             self.game.state.run_update(dt, &mut self.resources);
         }
 
+        let found = match self.deal_search {
+            Some(ref rx) => match rx.try_recv() {
+                Ok(found) => Some(found),
+                Err(TryRecvError::Empty) => return Ok(()),
+                Err(TryRecvError::Disconnected) => None,
+            },
+            None => return Ok(()),
+        };
+        self.deal_search = None;
+        if let Some((seed, rating)) = found {
+            self.game.seed = seed;
+            self.rating = rating;
+        }
+        if self.waiting {
+            self.waiting = false;
+            self.start(ctx);
+        }
+
         Ok(())
     }
 
     fn draw(&mut self, ctx: &mut Context) -> GameResult<()> {
-        self.game.state.run_render(ctx, &mut self.resources)?;
+        self.game
+            .state
+            .run_render(ctx, &mut self.resources, self.game.seed)?;
 
-        let text = self
-            .resources
-            .get_text(ctx, "Click anywhere to start a new game.")?;
-        let pos = Point2::from([
-            640.0 - text.width(ctx) as f32 / 2.0,
-            403.0 - text.height(ctx) as f32 / 2.0,
-        ]);
-        graphics::draw(ctx, text, DrawParam::new().dest(pos))?;
+        let mut lines = Vec::new();
+        if self.waiting {
+            lines.push("Looking for a suitable deal... Press Escape to keep this one.".to_string());
+        } else {
+            lines.push("Click anywhere to start a new game.".to_string());
+        }
+        if let Some(elapsed) = self.saved.as_ref().map(|save| save.elapsed) {
+            lines.push(format!(
+                "Press R to resume your saved game ({}:{:02}).",
+                elapsed as u32 / 60,
+                elapsed as u32 % 60
+            ));
+        }
+        lines.push(format!(
+            "Press W to deal only winnable games ({}).",
+            if self.resources.settings.winnable_only {
+                "on"
+            } else {
+                "off"
+            }
+        ));
+        lines.push(format!(
+            "Press D to choose the difficulty ({}).",
+            match self.resources.settings.difficulty {
+                Some(d) => d.to_string(),
+                None => "any".to_string(),
+            }
+        ));
+        lines.push(format!(
+            "Press A to choose which cards move on their own ({}).",
+            self.resources.settings.automove
+        ));
+        lines.push(format!(
+            "Press V to choose the rules ({}).",
+            self.game.variant
+        ));
+        if self.deal_search.is_none() {
+            lines.push(match self.rating {
+                Some(d) => format!("This deal is rated {}.", d),
+                None => "This deal could not be rated.".to_string(),
+            });
+        } else if !self.replaces_deal {
+            lines.push("Rating this deal...".to_string());
+        }
+
+        let table = self.resources.layout.size;
+        let mut y = None;
+        for line in &lines {
+            let text = self.resources.get_text(ctx, line)?;
+            let top = y.unwrap_or(table.y / 2.0 - text.height(ctx) as f32 / 2.0);
+            let pos = Point2::from([table.x / 2.0 - text.width(ctx) as f32 / 2.0, top]);
+            graphics::draw(ctx, text, DrawParam::new().dest(pos))?;
+            y = Some(top + text.height(ctx) as f32);
+        }
 
         graphics::present(ctx)?;
         Ok(())
     }
 
+    fn resize_event(&mut self, ctx: &mut Context, width: f32, height: f32) {
+        self.resources.viewport.resize(ctx, width, height);
+    }
+
     fn mouse_button_down_event(
         &mut self,
         ctx: &mut Context,
@@ -75,30 +312,52 @@ impl EventHandler for WelcomeState {
         _y: f32,
     ) {
         if !self.game.state.busy() {
-            self.move_on = true;
-            ggez::event::quit(ctx);
+            if self.saved.take().is_some() {
+                SaveGame::delete(ctx);
+            }
+            self.start(ctx);
+        }
+    }
+
+    fn key_down_event(
+        &mut self,
+        ctx: &mut Context,
+        keycode: KeyCode,
+        _keymod: KeyMods,
+        _repeat: bool,
+    ) {
+        match keycode {
+            KeyCode::Escape if self.waiting => self.cancel_waiting(),
+            KeyCode::Escape => {
+                self.stop_search();
+                ggez::event::quit(ctx);
+            }
+            KeyCode::W => self.toggle_winnable_only(ctx),
+            KeyCode::D => self.cycle_difficulty(ctx),
+            KeyCode::A => self.cycle_automove(ctx),
+            KeyCode::V if !self.game.state.busy() => self.cycle_variant(ctx),
+            KeyCode::R => {
+                if let Some(save) = self.saved.take() {
+                    self.game = Game::from_save(&save, &self.resources.layout).unwrap();
+                    self.resumed = Some(save.elapsed);
+                    self.stop_search();
+                    self.move_on = true;
+                    ggez::event::quit(ctx);
+                }
+            }
+            _ => {}
         }
     }
 }
 
 impl From<VictoryState> for WelcomeState {
     fn from(old: VictoryState) -> WelcomeState {
-        WelcomeState {
-            resources: old.resources,
-            move_on: false,
-            game: Game::new(),
-            ready: true,
-        }
+        WelcomeState::with_resources(old.resources, None)
     }
 }
 
 impl From<GiveupState> for WelcomeState {
     fn from(old: GiveupState) -> WelcomeState {
-        WelcomeState {
-            resources: old.resources,
-            move_on: false,
-            game: Game::new(),
-            ready: true,
-        }
+        WelcomeState::with_resources(old.resources, None)
     }
 }
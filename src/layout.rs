@@ -0,0 +1,294 @@
+//! Where the stacks and buttons are on the table.
+//!
+//! Layouts are text files in the `layouts` directory, see `resources/layouts/standard.txt` for
+//! the format. The standard layout is built in, so the game runs without any of them.
+
+use std::error::Error;
+use std::fmt;
+use std::io::Read;
+use std::str::FromStr;
+
+use ggez::{filesystem, Context};
+
+use types::*;
+use variant::Variant;
+
+const LAYOUT_DIR: &str = "/layouts";
+const STANDARD: &str = include_str!("../resources/layouts/standard.txt");
+
+const ROLES: [StackRole; 4] = [
+    StackRole::Sorting,
+    StackRole::Dragon,
+    StackRole::Flower,
+    StackRole::Target,
+];
+const COLORS: [Color; 3] = [Color::Red, Color::Green, Color::White];
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Layout {
+    /// Resource path of the background image.
+    pub table_image: String,
+    /// Width and height of the table, everything on it is placed in these coordinates.
+    pub size: Vector2,
+    /// Top left corners of the stacks, in the order they are listed.
+    pub stacks: Vec<(StackRole, Point2)>,
+    /// Centers of the dragon buttons.
+    pub buttons: Vec<(Color, Point2)>,
+    pub shifts: StackShifts,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum LayoutError {
+    /// A line that could not be read, counting from 1.
+    BadLine(usize),
+    MissingTable,
+    MissingSize,
+    /// The layout does not have as many stacks of the role as the game uses.
+    StackCount(StackRole),
+    /// There is no button of the color, or more than one.
+    ButtonCount(Color),
+    /// The cards of the flower stack are not shifted up or down, the shuffle needs them to be.
+    FlowerShift,
+}
+
+impl fmt::Display for LayoutError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            LayoutError::BadLine(line) => write!(
+                f,
+                "line {}: expected `table`, `size`, `stack`, `button` or `shift` and its values",
+                line
+            ),
+            LayoutError::MissingTable => write!(f, "missing table image"),
+            LayoutError::MissingSize => write!(f, "missing table size"),
+            LayoutError::StackCount(role) => {
+                write!(f, "expected {} {} stacks", stacks_needed(role), role)
+            }
+            LayoutError::ButtonCount(color) => {
+                write!(f, "expected one {} button", color_name(color))
+            }
+            LayoutError::FlowerShift => write!(f, "the flower stack needs a vertical shift"),
+        }
+    }
+}
+
+impl Error for LayoutError {}
+
+impl Default for Layout {
+    fn default() -> Layout {
+        STANDARD.parse().expect("the standard layout is broken")
+    }
+}
+
+impl Layout {
+    /// Read the layout `name` from the layouts directory. Falls back to the standard layout if
+    /// it can not be read.
+    pub fn load(ctx: &mut Context, name: &str) -> Layout {
+        let path = format!("{}/{}.txt", LAYOUT_DIR, name);
+        match Layout::read(ctx, &path) {
+            Ok(layout) => layout,
+            Err(e) => {
+                warn!("Using the standard layout, could not load {}: {}", path, e);
+                Layout::default()
+            }
+        }
+    }
+
+    fn read(ctx: &mut Context, path: &str) -> Result<Layout, Box<dyn Error>> {
+        let mut string = String::new();
+        filesystem::open(ctx, path)?.read_to_string(&mut string)?;
+        Ok(string.parse()?)
+    }
+
+    /// Positions of the stacks of a role.
+    pub fn stacks(&self, role: StackRole) -> impl Iterator<Item = Point2> + '_ {
+        self.stacks
+            .iter()
+            .filter(move |&&(r, _)| r == role)
+            .map(|&(_, pos)| pos)
+    }
+}
+
+impl FromStr for Layout {
+    type Err = LayoutError;
+
+    fn from_str(s: &str) -> Result<Layout, LayoutError> {
+        let mut table_image = None;
+        let mut size = None;
+        let mut stacks = Vec::new();
+        let mut buttons = Vec::new();
+        let mut shifts = StackShifts::default();
+
+        for (i, line) in s.lines().enumerate() {
+            let bad = || LayoutError::BadLine(i + 1);
+            let line = line.split('#').next().unwrap_or("");
+            match *line.split_whitespace().collect::<Vec<_>>() {
+                [] => {}
+                ["table", image] => table_image = Some(image.to_string()),
+                ["size", w, h] => {
+                    let corner = parse_point(w, h).filter(|p| p.x > 0.0 && p.y > 0.0);
+                    size = Some(corner.ok_or_else(bad)?.coords);
+                }
+                ["stack", role, x, y] => {
+                    let role = role.parse().map_err(|_| bad())?;
+                    stacks.push((role, parse_point(x, y).ok_or_else(bad)?));
+                }
+                ["button", color, x, y] => {
+                    let color = COLORS
+                        .iter()
+                        .cloned()
+                        .find(|&c| color_name(c) == color)
+                        .ok_or_else(bad)?;
+                    buttons.push((color, parse_point(x, y).ok_or_else(bad)?));
+                }
+                ["shift", role, dx, dy] => {
+                    let role = role.parse().map_err(|_| bad())?;
+                    let shift = parse_point(dx, dy).ok_or_else(bad)?;
+                    *shifts.get_mut(role).ok_or_else(bad)? = shift.coords;
+                }
+                _ => return Err(bad()),
+            }
+        }
+
+        let layout = Layout {
+            table_image: table_image.ok_or(LayoutError::MissingTable)?,
+            size: size.ok_or(LayoutError::MissingSize)?,
+            stacks,
+            buttons,
+            shifts,
+        };
+        for &role in &ROLES {
+            if layout.stacks(role).count() != stacks_needed(role) {
+                return Err(LayoutError::StackCount(role));
+            }
+        }
+        for &color in &COLORS {
+            if layout.buttons.iter().filter(|&&(c, _)| c == color).count() != 1 {
+                return Err(LayoutError::ButtonCount(color));
+            }
+        }
+        if layout.shifts.flower.y == 0.0 {
+            return Err(LayoutError::FlowerShift);
+        }
+        Ok(layout)
+    }
+}
+
+/// How many stacks of a role the variant with the most of them uses.
+fn stacks_needed(role: StackRole) -> usize {
+    Variant::ALL
+        .iter()
+        .map(|v| v.table().into_iter().filter(|&r| r == role).count())
+        .max()
+        .unwrap_or(0)
+}
+
+fn color_name(color: Color) -> &'static str {
+    match color {
+        Color::Red => "red",
+        Color::Green => "green",
+        Color::White => "white",
+    }
+}
+
+fn parse_point(x: &str, y: &str) -> Option<Point2> {
+    let p = Point2::new(x.parse().ok()?, y.parse().ok()?);
+    if p.x.is_finite() && p.y.is_finite() {
+        Some(p)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The standard layout with `from` replaced by `to`.
+    fn standard_with(from: &str, to: &str) -> Result<Layout, LayoutError> {
+        assert!(STANDARD.contains(from));
+        STANDARD.replacen(from, to, 1).parse()
+    }
+
+    #[test]
+    fn standard() {
+        let layout = Layout::default();
+        assert_eq!(layout.table_image, "/textures/solitaire/table_large.png");
+        assert_eq!(layout.size, Vector2::new(1280.0, 806.0));
+        assert_eq!(layout.stacks.len(), 15);
+        assert_eq!(
+            layout.stacks(StackRole::Flower).collect::<Vec<_>>(),
+            vec![Point2::new(614.0, 20.0)]
+        );
+        assert_eq!(layout.buttons[0], (Color::Red, Point2::new(533.0, 54.0)));
+        assert_eq!(layout.shifts, StackShifts::default());
+    }
+
+    #[test]
+    fn bad_lines() {
+        let bad = LayoutError::BadLine(12);
+        let line = "table /textures/solitaire/table_large.png";
+        assert_eq!(standard_with(line, "table"), Err(bad.clone()));
+        assert_eq!(standard_with(line, "chair 1 2"), Err(bad.clone()));
+        let line = "size 1280 806";
+        assert_eq!(
+            standard_with(line, "size 1280 0"),
+            Err(LayoutError::BadLine(13))
+        );
+        let line = "stack dragon 45 20";
+        assert_eq!(
+            standard_with(line, "stack joker 45 20"),
+            Err(LayoutError::BadLine(15))
+        );
+        assert_eq!(
+            standard_with(line, "stack dragon 45"),
+            Err(LayoutError::BadLine(15))
+        );
+        let line = "button red 533 54";
+        assert_eq!(
+            standard_with(line, "button blue 533 54"),
+            Err(LayoutError::BadLine(32))
+        );
+        let line = "shift sorting 0 32";
+        let err = Err(LayoutError::BadLine(36));
+        assert_eq!(standard_with(line, "shift sorting 0 inf"), err);
+        assert_eq!(standard_with(line, "shift sorting NaN 32"), err);
+        assert_eq!(standard_with(line, "shift generic 0 32"), err);
+    }
+
+    #[test]
+    fn missing() {
+        let line = "table /textures/solitaire/table_large.png";
+        assert_eq!(standard_with(line, ""), Err(LayoutError::MissingTable));
+        assert_eq!(
+            standard_with("size 1280 806", ""),
+            Err(LayoutError::MissingSize)
+        );
+        assert_eq!(
+            standard_with("stack flower 614 20", ""),
+            Err(LayoutError::StackCount(StackRole::Flower))
+        );
+        assert_eq!(
+            standard_with("stack target 805 20", "stack sorting 805 20"),
+            Err(LayoutError::StackCount(StackRole::Sorting))
+        );
+        assert_eq!(
+            standard_with("button green 533 137", ""),
+            Err(LayoutError::ButtonCount(Color::Green))
+        );
+        assert_eq!(
+            standard_with("button green 533 137", "button white 533 137"),
+            Err(LayoutError::ButtonCount(Color::Green))
+        );
+    }
+
+    #[test]
+    fn flower_shift() {
+        let line = "shift flower 0.1 -0.25";
+        assert_eq!(
+            standard_with(line, "shift flower 0.1 0"),
+            Err(LayoutError::FlowerShift)
+        );
+        assert!(standard_with(line, "shift flower 0 1").is_ok());
+    }
+}
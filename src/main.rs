@@ -5,20 +5,23 @@ extern crate env_logger;
 extern crate ggez;
 #[macro_use]
 extern crate log;
-extern crate rand;
 extern crate rodio;
+extern crate solitaire_core;
 
 mod gamestates;
 
-mod ai;
 //mod custom_audio;
 mod cs;
 mod game;
+mod layout;
 mod resources;
-mod rules;
+mod settings;
+mod storage;
 mod types;
 mod utils;
 
+use solitaire_core::{ai, board, deal, record, rules, savegame, variant};
+
 use std::env;
 
 use ggez::{conf, ContextBuilder};
@@ -27,11 +30,57 @@ use gamestates::GameWrapper;
 
 const SHENZHEN_PATH: &str = ".local/share/Steam/steamapps/common/SHENZHEN IO/Content/";
 
+/// Parse a deal seed as printed in the HUD (hexadecimal, optionally prefixed with `0x`).
+fn parse_seed(s: &str) -> Option<u64> {
+    u64::from_str_radix(s.trim().trim_start_matches("0x"), 16).ok()
+}
+
+const USAGE: &str = "Usage: solitaire [SEED | --replay FILE]";
+
+/// What to do after the window opens.
+enum Start {
+    Play(Option<u64>),
+    Replay(record::Record),
+}
+
+fn parse_args(args: &[String]) -> Result<Start, String> {
+    match args {
+        [] => Ok(Start::Play(None)),
+        [flag, path] if flag == "--replay" => {
+            let text = std::fs::read_to_string(path)
+                .map_err(|e| format!("Could not read {}: {}", path, e))?;
+            record::Record::parse(&text)
+                .map(Start::Replay)
+                .map_err(|e| format!("Invalid game record {}: {}", path, e))
+        }
+        [arg] if !arg.starts_with('-') => match parse_seed(arg) {
+            Some(seed) => Ok(Start::Play(Some(seed))),
+            None => Err(format!("Invalid deal seed: {}", arg)),
+        },
+        _ => Err(format!("Invalid arguments: {}", args.join(" "))),
+    }
+}
+
 fn main() {
     env_logger::init();
 
+    let args: Vec<String> = env::args().skip(1).collect();
+    let start = match parse_args(&args) {
+        Ok(start) => start,
+        Err(msg) => {
+            eprintln!("{}", msg);
+            eprintln!("{}", USAGE);
+            return;
+        }
+    };
+
+    // the window is fitted to the table once the layout is loaded
+    let table = layout::Layout::default().size;
     let c = conf::Conf {
-        window_mode: conf::WindowMode::default().dimensions(1280.0, 806.0),
+        window_mode: conf::WindowMode::default()
+            .dimensions(table.x, table.y)
+            .resizable(true)
+            .min_dimensions(table.x / 4.0, table.y / 4.0),
         window_setup: conf::WindowSetup::default().title("Solitaire Clone"),
         backend: conf::Backend::OpenGL { major: 3, minor: 2 },
         ..conf::Conf::default()
@@ -57,7 +106,11 @@ fn main() {
         .build()
         .unwrap();
 
-    let mut state = GameWrapper::new(&mut ctx).unwrap();
+    let mut state = match start {
+        Start::Play(seed) => GameWrapper::new(&mut ctx, seed),
+        Start::Replay(record) => GameWrapper::replay(&mut ctx, &record),
+    }
+    .unwrap();
     loop {
         if let GameWrapper::Quit = state {
             break;
@@ -6,19 +6,15 @@ use ggez::audio::{SoundSource, Source};
 use ggez::graphics::{Font, Image, Scale, Text, TextFragment};
 use ggez::*;
 
-use types::{ButtonState, Color};
-
-#[derive(Copy, Clone, Debug, PartialEq)]
-pub enum Sounds {
-    None,
-    //Pickup,
-    Place,
-    Sweep,
-    Deal,
-}
+use layout::Layout;
+use settings::Settings;
+use types::{ButtonState, Color, Sounds};
+use utils::viewport::Viewport;
 
 pub struct Resources {
     wins: u32,
+    pub settings: Settings,
+    pub layout: Layout,
     pub table_image: Image,
     pub card_front: Image,
     pub card_back: Image,
@@ -38,6 +34,7 @@ pub struct Resources {
     pub deal_sound: Audio,
     pub sweep_sound: Audio,
     pub music: Audio,
+    pub viewport: Viewport,
 }
 
 impl Resources {
@@ -180,9 +177,25 @@ impl Resources {
             Image::new(ctx, "/textures/solitaire/button_white_down.png")?,
         );
 
+        let settings = Settings::load(ctx)?;
+        let layout = match settings.layout {
+            Some(ref name) => Layout::load(ctx, name),
+            None => Layout::default(),
+        };
+        let table_image = Image::new(ctx, &layout.table_image)?;
+        let mut viewport = Viewport::new(layout.size);
+        if layout.size != Layout::default().size {
+            if let Err(e) = graphics::set_drawable_size(ctx, layout.size.x, layout.size.y) {
+                error!("Could not fit the window to the table: {}", e);
+            }
+            viewport.resize(ctx, layout.size.x, layout.size.y);
+        }
+
         let r = Resources {
             wins: Resources::load_wins(ctx)?,
-            table_image: Image::new(ctx, "/textures/solitaire/table_large.png")?,
+            settings,
+            layout,
+            table_image,
             card_front: Image::new(ctx, "/textures/solitaire/card_front.png")?,
             card_back: Image::new(ctx, "/textures/solitaire/card_back.png")?,
             numbers,
@@ -201,6 +214,7 @@ impl Resources {
             deal_sound: Audio::new(ctx, "/sounds/card_deal.wav")?,
             sweep_sound: Audio::new(ctx, "/sounds/card_sweep.wav")?,
             music: Audio::new(ctx, "/music/Solitaire.ogg")?,
+            viewport,
         };
         Ok(r)
     }
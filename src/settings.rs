@@ -0,0 +1,121 @@
+use std::io::{Read, Write};
+
+use ggez::{filesystem, Context, GameError, GameResult};
+
+use ai::Difficulty;
+use rules::AutomovePolicy;
+use variant::Variant;
+
+const SETTINGS_FILE: &str = "/settings.txt";
+
+/// Player preferences, stored in the user data directory as `key value` lines.
+#[derive(Clone, Debug, Default)]
+pub struct Settings {
+    /// Only deal games the solver has proven winnable.
+    pub winnable_only: bool,
+    /// Only deal games of this difficulty. Implies `winnable_only`.
+    pub difficulty: Option<Difficulty>,
+    /// Which cards move to the target stacks on their own.
+    pub automove: AutomovePolicy,
+    /// The rules new games are dealt with.
+    pub variant: Variant,
+    /// The name of the table layout, the standard one if there is none.
+    pub layout: Option<String>,
+}
+
+impl Settings {
+    pub fn load(ctx: &mut Context) -> GameResult<Settings> {
+        let mut string = String::new();
+        match filesystem::open(ctx, SETTINGS_FILE) {
+            Ok(mut f) => f.read_to_string(&mut string)?,
+            Err(GameError::ResourceNotFound(_, _)) => return Ok(Settings::default()),
+            Err(e) => return Err(e),
+        };
+        Ok(Settings::parse(&string))
+    }
+
+    pub fn store(&self, ctx: &mut Context) -> GameResult<()> {
+        let mut f = filesystem::create(ctx, SETTINGS_FILE)?;
+        writeln!(f, "winnable_only {}", self.winnable_only)?;
+        match self.difficulty {
+            Some(d) => writeln!(f, "difficulty {}", d)?,
+            None => writeln!(f, "difficulty any")?,
+        }
+        writeln!(f, "automove {}", self.automove)?;
+        writeln!(f, "variant {}", self.variant)?;
+        if let Some(ref layout) = self.layout {
+            writeln!(f, "layout {}", layout)?;
+        }
+        Ok(())
+    }
+
+    /// Unknown or malformed lines are skipped, so older versions can read newer files.
+    fn parse(s: &str) -> Settings {
+        let mut settings = Settings::default();
+        for line in s.lines() {
+            let mut tokens = line.split_whitespace();
+            match (tokens.next(), tokens.next()) {
+                (Some("winnable_only"), Some(value)) => {
+                    settings.winnable_only = value.parse().unwrap_or(settings.winnable_only)
+                }
+                // "any" is not a difficulty and turns the filter off
+                (Some("difficulty"), Some(value)) => settings.difficulty = value.parse().ok(),
+                (Some("automove"), Some(value)) => {
+                    settings.automove = value.parse().unwrap_or(settings.automove)
+                }
+                (Some("variant"), Some(value)) => {
+                    settings.variant = value.parse().unwrap_or(settings.variant)
+                }
+                (Some("layout"), Some(value)) => settings.layout = Some(value.to_string()),
+                (None, _) => {}
+                _ => warn!("Ignoring setting: {}", line),
+            }
+        }
+        settings
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn defaults() {
+        let settings = Settings::parse("");
+        assert!(!settings.winnable_only);
+        assert_eq!(settings.difficulty, None);
+        assert_eq!(settings.automove, AutomovePolicy::Safe);
+        assert_eq!(settings.variant, Variant::Standard);
+        assert_eq!(settings.layout, None);
+    }
+
+    #[test]
+    fn values() {
+        let settings = Settings::parse(
+            "winnable_only true\ndifficulty hard\nautomove off\nvariant two-cells\nlayout wide\n",
+        );
+        assert!(settings.winnable_only);
+        assert_eq!(settings.difficulty, Some(Difficulty::Hard));
+        assert_eq!(settings.automove, AutomovePolicy::Off);
+        assert_eq!(settings.variant, Variant::TwoCells);
+        assert_eq!(settings.layout, Some("wide".to_string()));
+    }
+
+    #[test]
+    fn bad_lines() {
+        let settings = Settings::parse(
+            "volume 11\n\nwinnable_only yes\ndifficulty any\nautomove always\nvariant spider\n\
+             layout\n",
+        );
+        assert!(!settings.winnable_only);
+        assert_eq!(settings.difficulty, None);
+        assert_eq!(settings.automove, AutomovePolicy::Safe);
+        assert_eq!(settings.variant, Variant::Standard);
+        assert_eq!(settings.layout, None);
+
+        // a bad value keeps what an earlier line set
+        let settings = Settings::parse("variant no-flower\nvariant spider\ndifficulty foo\n");
+        assert_eq!(settings.variant, Variant::NoFlower);
+        assert_eq!(settings.difficulty, None);
+    }
+}
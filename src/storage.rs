@@ -0,0 +1,67 @@
+use std::io::{Read, Write};
+
+use ggez::{filesystem, Context, GameError, GameResult};
+
+use record::Record;
+use savegame::SaveGame;
+
+const SAVE_FILE: &str = "/savegame.txt";
+const RECORD_DIR: &str = "/records";
+
+/// Keeping the interrupted game in the user data directory.
+pub trait SaveGameFile: Sized {
+    fn load(ctx: &mut Context) -> GameResult<Option<Self>>;
+    fn store(&self, ctx: &mut Context) -> GameResult<()>;
+    fn delete(ctx: &mut Context);
+}
+
+impl SaveGameFile for SaveGame {
+    fn load(ctx: &mut Context) -> GameResult<Option<SaveGame>> {
+        let mut string = String::new();
+        match filesystem::open(ctx, SAVE_FILE) {
+            Ok(mut f) => f.read_to_string(&mut string)?,
+            Err(GameError::ResourceNotFound(_, _)) => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        match SaveGame::parse(&string) {
+            Some(save) => Ok(Some(save)),
+            None => {
+                warn!("Ignoring corrupt save game");
+                Ok(None)
+            }
+        }
+    }
+
+    fn store(&self, ctx: &mut Context) -> GameResult<()> {
+        let mut f = filesystem::create(ctx, SAVE_FILE)?;
+        f.write_all(self.to_string().as_bytes())?;
+        Ok(())
+    }
+
+    fn delete(ctx: &mut Context) {
+        if filesystem::exists(ctx, SAVE_FILE) {
+            if let Err(e) = filesystem::delete(ctx, SAVE_FILE) {
+                warn!("Could not delete save game: {}", e);
+            }
+        }
+    }
+}
+
+/// Keeping the records of finished games in the user data directory, one file per deal.
+pub trait RecordFile {
+    fn store(&self, ctx: &mut Context) -> GameResult<()>;
+}
+
+impl RecordFile for Record {
+    /// Store the record next to the records of other deals.
+    fn store(&self, ctx: &mut Context) -> GameResult<()> {
+        filesystem::create_dir(ctx, RECORD_DIR)?;
+        let mut f = filesystem::create(ctx, record_path(self.seed))?;
+        f.write_all(self.to_string().as_bytes())?;
+        Ok(())
+    }
+}
+
+fn record_path(seed: u64) -> String {
+    format!("{}/{:016x}.txt", RECORD_DIR, seed)
+}
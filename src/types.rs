@@ -1,8 +1,6 @@
-use std::slice;
-
 use ggez::graphics;
 
-pub use resources::Sounds;
+pub use solitaire_core::types::*;
 
 pub const CARD_WIDTH: f32 = 123.0;
 pub const CARD_HEIGHT: f32 = 233.0;
@@ -22,22 +20,21 @@ impl Entity {
     }
 }
 
-#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
-pub enum Color {
-    Red,
-    Green,
-    White,
+/// Colors of the card faces and icons, which the rules engine knows nothing about.
+pub trait CardColors {
+    fn to_font_color(&self) -> graphics::Color;
+    fn to_icon_color(&self) -> graphics::Color;
 }
 
-impl Color {
-    pub fn to_font_color(&self) -> graphics::Color {
+impl CardColors for Color {
+    fn to_font_color(&self) -> graphics::Color {
         match *self {
             Color::Red => graphics::Color::new(0.7, 0.2, 0.1, 1.0),
             Color::Green => graphics::Color::new(0.1, 0.4, 0.3, 1.0),
             Color::White => graphics::Color::new(0.1, 0.1, 0.1, 1.0),
         }
     }
-    pub fn to_icon_color(&self) -> graphics::Color {
+    fn to_icon_color(&self) -> graphics::Color {
         match *self {
             Color::Red => graphics::Color::new(1.0, 1.0, 1.0, 1.0),
             Color::Green => graphics::Color::new(0.1, 0.4, 0.3, 1.0),
@@ -46,6 +43,15 @@ impl Color {
     }
 }
 
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Sounds {
+    None,
+    //Pickup,
+    Place,
+    Sweep,
+    Deal,
+}
+
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
 pub enum ButtonState {
     Active,
@@ -70,84 +76,73 @@ impl Button {
     }
 }
 
-#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
-pub enum Suite {
-    FaceDown,
-    Flower,
-    Dragon(Color),
-    Number(u8, Color),
-}
-
-#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
-pub enum StackRole {
-    Dragon,
-    Flower,
-    Target,
-    Sorting,
-    Generic,
-    Animation,
+/// How far each card of a stack is drawn from the one below it, for each role of a stack on the
+/// table. Cards being dragged are spread like a sorting stack.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct StackShifts {
+    pub sorting: Vector2,
+    pub dragon: Vector2,
+    pub flower: Vector2,
+    pub target: Vector2,
 }
 
-#[derive(Clone, Hash, Eq, PartialEq, Debug)]
-pub struct Stack {
-    pub cards: Vec<Suite>,
-    pub role: StackRole,
-}
-
-impl Stack {
-    pub fn new(role: StackRole) -> Stack {
-        Stack {
-            cards: Vec::new(),
-            role,
+impl Default for StackShifts {
+    fn default() -> StackShifts {
+        StackShifts {
+            sorting: Vector2::new(0.0, 32.0),
+            dragon: Vector2::new(0.1, -0.25),
+            flower: Vector2::new(0.1, -0.25),
+            target: Vector2::new(0.1, -0.25),
         }
     }
+}
 
-    pub fn len(&self) -> usize {
-        self.cards.len()
-    }
-
-    pub fn iter(&self) -> slice::Iter<Suite> {
-        self.cards.iter()
-    }
-
-    pub fn top(&self) -> Option<Suite> {
-        self.cards.last().map(|s| *s)
-    }
-
-    pub fn push_card(&mut self, card: Suite) {
-        self.cards.push(card);
-    }
-
-    pub fn pop_card(&mut self) -> Option<Suite> {
-        self.cards.pop()
+impl StackShifts {
+    pub fn get(&self, role: StackRole) -> Vector2 {
+        match role {
+            StackRole::Dragon => self.dragon,
+            StackRole::Flower => self.flower,
+            StackRole::Target => self.target,
+            StackRole::Sorting | StackRole::Generic => self.sorting,
+            StackRole::Animation => Vector2::new(0.0, 0.0),
+        }
     }
 
-    pub fn extend(&mut self, other: Stack) {
-        self.cards.extend(other.cards);
+    /// The shift of a role on the table, `None` for the roles which are derived from them.
+    pub fn get_mut(&mut self, role: StackRole) -> Option<&mut Vector2> {
+        match role {
+            StackRole::Dragon => Some(&mut self.dragon),
+            StackRole::Flower => Some(&mut self.flower),
+            StackRole::Target => Some(&mut self.target),
+            StackRole::Sorting => Some(&mut self.sorting),
+            StackRole::Generic | StackRole::Animation => None,
+        }
     }
+}
 
-    pub fn peek(&self, idx: usize) -> Suite {
-        self.cards[idx]
-    }
+pub trait StackShift {
+    fn get_stackshift(&self, shifts: &StackShifts) -> Vector2;
+}
 
-    pub fn get_stackshift(&self) -> Vector2 {
-        match self.role {
-            StackRole::Dragon => Vector2::new(0.1, -0.25),
-            //StackRole::DragonLocked => Vector2::new(0.1, -0.25),
-            StackRole::Flower => Vector2::new(0.1, -0.25),
-            StackRole::Target => Vector2::new(0.1, -0.25),
-            StackRole::Sorting => Vector2::new(0.0, 32.0),
-            StackRole::Generic => Vector2::new(0.0, 32.0),
-            StackRole::Animation => Vector2::new(0.0, 0.0),
-        }
+impl StackShift for Stack {
+    fn get_stackshift(&self, shifts: &StackShifts) -> Vector2 {
+        shifts.get(self.role)
     }
+}
 
-    pub fn split(&mut self, at: usize) -> Stack {
-        Stack {
-            cards: self.cards.split_off(at),
-            role: StackRole::Generic,
-        }
-    }
+/// A single change to the table, recorded so that it can be undone and redone.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Action {
+    Cards {
+        src: Entity,
+        dst: Entity,
+        n: usize,
+    },
+    Dragons {
+        color: Color,
+        target: Entity,
+        sources: [Entity; 4],
+    },
 }
 
 pub struct Animation {
@@ -36,17 +36,52 @@ impl BoundingBox {
         x >= self.topleft.x && y >= self.topleft.y && x <= self.bottomright.x && y <= self.bottomright.y
     }*/
 
-    pub fn intersects(&self, other: &BoundingBox) -> bool {
-        let ax = self.topleft.x + self.bottomright.x;
-        let ay = self.topleft.y + self.bottomright.y;
-        let bx = other.topleft.x + other.bottomright.x;
-        let by = other.topleft.y + other.bottomright.y;
-
-        let aw = self.bottomright.x - self.topleft.x;
-        let ah = self.bottomright.y - self.topleft.y;
-        let bw = other.bottomright.x - other.topleft.x;
-        let bh = other.bottomright.y - other.topleft.y;
-
-        f32::abs(ax - bx) < (aw + bw) && f32::abs(ay - by) < (ah + bh)
+    /// Area covered by both boxes, zero if they do not intersect.
+    pub fn overlap(&self, other: &BoundingBox) -> f32 {
+        let w = self.bottomright.x.min(other.bottomright.x) - self.topleft.x.max(other.topleft.x);
+        let h = self.bottomright.y.min(other.bottomright.y) - self.topleft.y.max(other.topleft.y);
+        w.max(0.0) * h.max(0.0)
+    }
+}
+
+/// Keys of the boxes that overlap `area`, the one covering the most of it first.
+pub fn by_overlap<K, I>(area: &BoundingBox, boxes: I) -> Vec<K>
+where
+    I: IntoIterator<Item = (K, BoundingBox)>,
+{
+    let mut overlaps = boxes
+        .into_iter()
+        .map(|(key, bb)| (key, bb.overlap(area)))
+        .filter(|&(_, overlap)| overlap > 0.0)
+        .collect::<Vec<_>>();
+    overlaps.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
+    overlaps.into_iter().map(|(key, _)| key).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn overlap() {
+        let a = BoundingBox::new(0.0, 10.0, 0.0, 20.0);
+        assert_eq!(a.overlap(&a), 200.0);
+        assert_eq!(a.overlap(&BoundingBox::new(5.0, 15.0, 10.0, 30.0)), 50.0);
+        assert_eq!(BoundingBox::new(5.0, 15.0, 10.0, 30.0).overlap(&a), 50.0);
+        // touching edges and boxes apart cover nothing
+        assert_eq!(a.overlap(&BoundingBox::new(10.0, 20.0, 0.0, 20.0)), 0.0);
+        assert_eq!(a.overlap(&BoundingBox::new(30.0, 40.0, 30.0, 40.0)), 0.0);
+    }
+
+    #[test]
+    fn ordering() {
+        let drag = BoundingBox::new(100.0, 200.0, 0.0, 100.0);
+        let boxes = vec![
+            ('a', BoundingBox::new(0.0, 120.0, 0.0, 100.0)),
+            ('b', BoundingBox::new(300.0, 400.0, 0.0, 100.0)),
+            ('c', BoundingBox::new(120.0, 220.0, 0.0, 100.0)),
+            ('d', BoundingBox::new(190.0, 290.0, 50.0, 150.0)),
+        ];
+        assert_eq!(by_overlap(&drag, boxes), vec!['c', 'a', 'd']);
     }
 }
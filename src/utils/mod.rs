@@ -1,2 +1,3 @@
 pub mod all;
 pub mod bbox;
+pub mod viewport;
@@ -0,0 +1,95 @@
+use ggez::graphics::{self, Rect};
+use ggez::Context;
+
+use types::*;
+
+/// Where the table is shown in the window.
+///
+/// The table is scaled uniformly to fit the window and centered in it, the bars left over on
+/// two sides stay black. Everything else keeps working in table coordinates.
+#[derive(Clone, Copy, Debug)]
+pub struct Viewport {
+    /// Size of the table, see `Layout::size`.
+    table: Vector2,
+    width: f32,
+    height: f32,
+}
+
+impl Viewport {
+    /// A window that shows exactly the table.
+    pub fn new(table: Vector2) -> Viewport {
+        Viewport {
+            table,
+            width: table.x,
+            height: table.y,
+        }
+    }
+
+    /// Fit the table into the window after it changed its size.
+    pub fn resize(&mut self, ctx: &mut Context, width: f32, height: f32) {
+        if width <= 0.0 || height <= 0.0 {
+            return; // minimized
+        }
+        self.width = width;
+        self.height = height;
+        if let Err(e) = graphics::set_screen_coordinates(ctx, self.screen_rect()) {
+            error!("Could not resize the table: {}", e);
+        }
+    }
+
+    fn scale(&self) -> f32 {
+        (self.width / self.table.x).min(self.height / self.table.y)
+    }
+
+    /// The area of the table coordinates that covers the whole window, bars included.
+    pub fn screen_rect(&self) -> Rect {
+        let w = self.width / self.scale();
+        let h = self.height / self.scale();
+        Rect::new((self.table.x - w) / 2.0, (self.table.y - h) / 2.0, w, h)
+    }
+
+    /// Table coordinates of a point in the window, e.g. of the mouse.
+    pub fn to_table(self, x: f32, y: f32) -> Point2 {
+        let r = self.screen_rect();
+        Point2::new(r.x + x / self.scale(), r.y + y / self.scale())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn window(width: f32, height: f32) -> Viewport {
+        Viewport {
+            width,
+            height,
+            ..Viewport::new(Vector2::new(1280.0, 800.0))
+        }
+    }
+
+    #[test]
+    fn fitting_window() {
+        let viewport = window(1280.0, 800.0);
+        assert_eq!(viewport.to_table(0.0, 0.0), Point2::new(0.0, 0.0));
+        assert_eq!(viewport.to_table(640.0, 400.0), Point2::new(640.0, 400.0));
+        assert_eq!(
+            viewport.to_table(640.0, 200.0),
+            window(640.0, 400.0).to_table(320.0, 100.0)
+        );
+    }
+
+    #[test]
+    fn bars() {
+        // twice as wide as needed: the table is in the middle with bars left and right
+        let wide = window(2560.0, 800.0);
+        assert_eq!(wide.screen_rect(), Rect::new(-640.0, 0.0, 2560.0, 800.0));
+        assert_eq!(wide.to_table(640.0, 0.0), Point2::new(0.0, 0.0));
+        assert_eq!(wide.to_table(0.0, 400.0), Point2::new(-640.0, 400.0));
+
+        // half as wide as the table is: it is scaled down, with bars above and below
+        let tall = window(640.0, 800.0);
+        assert_eq!(tall.screen_rect(), Rect::new(0.0, -400.0, 1280.0, 1600.0));
+        assert_eq!(tall.to_table(320.0, 400.0), Point2::new(640.0, 400.0));
+        assert_eq!(tall.to_table(0.0, 200.0), Point2::new(0.0, 0.0));
+    }
+}